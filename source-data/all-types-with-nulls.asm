@@ -0,0 +1,22 @@
+# The sample file from the Vertica documentation, with FloatCol null.
+# Build it with: verticareader assemble -o data/all-types-with-nulls.bin source-data/all-types-with-nulls.asm
+
+@signature
+@header 8 8 10 var 1 8 8 8 8 8 var 3 24 8
+
+@row
+@nulls 2
+int64:1                                 # IntCol
+char(10):one                            # CharCol
+varchar:ONE                             # VarCharCol
+bool:true                               # Bools
+date:1999-01-08                         # The_Date
+timestamp:"1999-02-23 03:11:52.35"      # TS_Elliot
+timestamptz:"1999-01-08 12:04:37"       # TS_TZ, in UTC
+time:07:09:23                           # Clock
+timetz:15:12:34-05                      # Clock_TZ
+varbinary:abcd                          # VB3
+binary(3):abcd                          # BiN
+00000000 00000000 00000000 00000000     # Num_Num_Num: 1234532
+64d61200 00000000
+interval:03:03:03                       # Space_Between
@@ -1,4 +1,10 @@
-use clap::Parser;
+use std::str::FromStr;
+
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::binary_encoding::BinaryEncoding;
+use crate::time_format::TimeFormat;
+use crate::time_zone::TimeZone;
 
 /// Convert Vertica native binary files to CSV/JSON
 #[derive(Parser, Debug)]
@@ -21,9 +27,19 @@ pub struct Args {
     pub types: String,
 
     /// +/- hours
-    #[arg(short = 'z', long, required = false, default_value_t = 0)]
+    #[arg(
+        short = 'z',
+        long,
+        required = false,
+        default_value_t = 0,
+        conflicts_with = "timezone"
+    )]
     pub tz_offset: i8,
 
+    /// Show timestamptz values in this zone, by name, like America/New_York, or by offset, like +05:30
+    #[arg(long, value_name = "ZONE")]
+    pub timezone: Option<TimeZone>,
+
     /// Field delimiter for CSV file [default: ,]
     #[arg(short, long, default_value_t = b',', hide_default_value = true)]
     pub delimiter: u8,
@@ -36,6 +52,19 @@ pub struct Args {
     #[arg(short, long)]
     pub single_quotes: bool,
 
+    /// Write nulls as this text in CSV, like \N, or NULL [default: nothing]
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = "",
+        hide_default_value = true
+    )]
+    pub null_string: String,
+
+    /// Quote empty values in CSV, so they can be told apart from nulls
+    #[arg(long)]
+    pub quote_empty: bool,
+
     /// Output in JSON format [default: CSV]
     #[arg(short = 'j', long = "json")]
     pub is_json: bool,
@@ -52,13 +81,94 @@ pub struct Args {
     #[arg(short, long, required = false, default_value_t = usize::MAX, hide_default_value=true)]
     pub limit: usize,
 
+    /// Skip the first <SKIP> rows of the file, without decoding them
+    #[arg(
+        long,
+        default_value_t = 0,
+        hide_default_value = true,
+        conflicts_with = "rows"
+    )]
+    pub skip: usize,
+
+    /// Only read rows A to B of the file, counting from 1; either end can be left off
+    #[arg(long, value_name = "A..B", value_parser = parse_row_range)]
+    pub rows: Option<RowRange>,
+
+    /// Only take every <EVERY>th row, starting with the first
+    #[arg(long, default_value_t = 1, hide_default_value = true)]
+    pub every: usize,
+
+    /// Take a random sample of <SAMPLE> rows, in the order they appear in the file
+    #[arg(long)]
+    pub sample: Option<usize>,
+
+    /// Seed for choosing the rows for --sample; the same seed always chooses the same rows
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
     /// Prefix hex strings with 0x
     #[arg(short = 'H', long)]
     pub hex_prefix: bool,
 
+    /// How to write binary and varbinary values
+    #[arg(long, value_enum, value_name = "ENCODING", default_value_t = BinaryEncoding::Hex)]
+    pub binary_encoding: BinaryEncoding,
+
+    /// Trim the zero bytes padding the end of binary(n) values
+    #[arg(long)]
+    pub trim_binary: bool,
+
+    /// Write this many digits of fractional seconds [default: the type's precision, or 6]
+    #[arg(long, value_name = "DIGITS", value_parser = clap::value_parser!(u32).range(0..=6))]
+    pub fractional_digits: Option<u32>,
+
+    /// Write dates and times like this: iso8601, rfc3339, epoch_s, epoch_ms, epoch_us, or a strftime format
+    #[arg(long, value_name = "FORMAT")]
+    pub time_format: Option<TimeFormat>,
+
+    /// How to write boolean values: 1/0, true/false, t/f, or TRUE/FALSE
+    #[arg(long, value_name = "FORMAT", default_value = "1/0")]
+    pub bool_format: BoolFormat,
+
+    /// How to write float values: shortest, fixed:N, or scientific
+    #[arg(long, value_name = "FORMAT", value_parser = parse_float_format, default_value = "shortest")]
+    pub float_format: FloatFormat,
+
+    /// Write NaN float values as this text
+    #[arg(long, value_name = "TEXT", default_value = "NaN")]
+    pub nan_as: String,
+
+    /// Write infinite float values as this text, after a minus sign if they're negative
+    #[arg(long, value_name = "TEXT", default_value = "Infinity")]
+    pub infinity_as: String,
+
+    /// How to write NaN and infinite float values in JSON
+    #[arg(long, value_enum, value_name = "HOW", default_value_t = JsonNonFinite::String)]
+    pub json_non_finite: JsonNonFinite,
+
+    /// How to write interval values
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = IntervalFormat::Vertica)]
+    pub interval_format: IntervalFormat,
+
     /// Maximum rows per file
     #[arg(short, long, default_value_t = usize::MAX, hide_default_value=true)]
     pub max_rows: usize,
+
+    /// Memory-map the input file, instead of reading it through a buffer
+    #[arg(long)]
+    pub mmap: bool,
+
+    /// Only output these columns, in this order; names or numbers, counting from 1
+    #[arg(short, long, value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// Only output rows matching this expression, e.g. "IntCol > 5 AND Name LIKE 'A%'"
+    #[arg(short = 'w', long = "where")]
+    pub filter: Option<String>,
+
+    /// Skip rows whose columns don't match their length, and carry on, instead of stopping
+    #[arg(long)]
+    pub skip_bad_rows: bool,
 }
 
 impl Args {
@@ -68,33 +178,447 @@ impl Args {
             output: None,
             types: "".to_string(),
             tz_offset: 0,
+            timezone: None,
             delimiter: b',',
             no_header: false,
             single_quotes: false,
+            null_string: "".to_string(),
+            quote_empty: false,
             is_json: false,
             is_json_lines: false,
             is_gzip: false,
             limit: usize::MAX,
+            skip: 0,
+            rows: None,
+            every: 1,
+            sample: None,
+            seed: 0,
             hex_prefix: false,
+            binary_encoding: BinaryEncoding::Hex,
+            trim_binary: false,
+            interval_format: IntervalFormat::Vertica,
+            bool_format: BoolFormat::Digits,
+            float_format: FloatFormat::Shortest,
+            nan_as: "NaN".to_string(),
+            infinity_as: "Infinity".to_string(),
+            json_non_finite: JsonNonFinite::String,
+            fractional_digits: None,
+            time_format: None,
             max_rows: usize::MAX,
+            mmap: false,
+            columns: vec![],
+            filter: None,
+            skip_bad_rows: false,
         }
     }
 
+    /// The time zone to show `TimestampTz` values in; `--timezone` if it was given, or else
+    /// `--tz-offset`.
+    pub fn time_zone(&self) -> TimeZone {
+        self.timezone
+            .unwrap_or_else(|| TimeZone::from_hours(self.tz_offset))
+    }
+
     pub fn with_most_defaults(input: String, output: Option<String>, types: String) -> Self {
         Self {
             input,
             output,
             types,
-            tz_offset: 0,
-            delimiter: b',',
-            no_header: false,
-            single_quotes: false,
-            is_json: false,
-            is_json_lines: false,
-            is_gzip: false,
-            limit: usize::MAX,
-            hex_prefix: false,
-            max_rows: usize::MAX,
+            ..Self::with_defaults()
+        }
+    }
+}
+
+/// How intervals are written out, chosen with `--interval-format`.
+// The variants have plain comments, because doc comments would turn `--help` into the long form.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum IntervalFormat {
+    // Like Vertica shows them: `1 02:03:04.500000`, or `1-02` for years and months
+    Vertica,
+    // ISO 8601 durations: `P1DT2H3M4.5S`, or `P1Y2M`
+    Iso8601,
+}
+
+/// How booleans are written out, chosen with `--bool-format`, or for a column in the types file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoolFormat {
+    /// `1` and `0`
+    Digits,
+    /// `true` and `false`
+    Words,
+    /// `t` and `f`, the way vsql writes them
+    Letters,
+    /// `TRUE` and `FALSE`
+    UpperWords,
+}
+
+impl BoolFormat {
+    /// The text for `value` in this format.
+    pub fn text(&self, value: bool) -> &'static str {
+        match (self, value) {
+            (BoolFormat::Digits, true) => "1",
+            (BoolFormat::Digits, false) => "0",
+            (BoolFormat::Words, true) => "true",
+            (BoolFormat::Words, false) => "false",
+            (BoolFormat::Letters, true) => "t",
+            (BoolFormat::Letters, false) => "f",
+            (BoolFormat::UpperWords, true) => "TRUE",
+            (BoolFormat::UpperWords, false) => "FALSE",
+        }
+    }
+}
+
+impl FromStr for BoolFormat {
+    type Err = String;
+
+    /// Parse a boolean format, which is the text for true, and then for false, like `t/f`.
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "1/0" => Ok(BoolFormat::Digits),
+            "true/false" => Ok(BoolFormat::Words),
+            "t/f" => Ok(BoolFormat::Letters),
+            "TRUE/FALSE" => Ok(BoolFormat::UpperWords),
+            _ => Err(format!(
+                "invalid boolean format: {}; use 1/0, true/false, t/f, or TRUE/FALSE",
+                format
+            )),
         }
     }
 }
+
+/// How floats are written out, chosen with `--float-format`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    /// The fewest digits that read back as the same value, like `0.1`, or `1500000`
+    Shortest,
+    /// Exactly this many digits after the decimal point, like `0.100` for `fixed:3`
+    Fixed(usize),
+    /// One digit before the decimal point, as few after it as needed, and an exponent of at
+    /// least two digits, like `1.5e+06`, the way `printf`'s `%e` writes them
+    Scientific,
+}
+
+/// Parse a float format, which is `shortest`, `fixed:N`, or `scientific`.
+fn parse_float_format(format: &str) -> Result<FloatFormat, String> {
+    match format {
+        "shortest" => Ok(FloatFormat::Shortest),
+        "scientific" => Ok(FloatFormat::Scientific),
+        _ => match format.strip_prefix("fixed:").map(|digits| digits.parse()) {
+            Some(Ok(digits)) => Ok(FloatFormat::Fixed(digits)),
+            Some(Err(_)) => Err(format!("invalid number of digits: {}", &format[6..])),
+            None => Err("expected shortest, fixed:N, or scientific".to_string()),
+        },
+    }
+}
+
+/// How NaN and infinite floats are written in JSON, which has no numbers for them, chosen with
+/// `--json-non-finite`.
+// The variants have plain comments, because doc comments would turn `--help` into the long form.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum JsonNonFinite {
+    // As strings, with the text from --nan-as and --infinity-as
+    String,
+    // As null
+    Null,
+}
+
+/// A range of rows, from `--rows`, counting from 1, and including both ends.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowRange {
+    pub first: usize,
+    /// The last row, or `usize::MAX` if the range runs to the end of the file
+    pub last: usize,
+}
+
+/// Parse a row range like `5..10`, `5..`, or `..10`.
+fn parse_row_range(range: &str) -> Result<RowRange, String> {
+    let (first, last) = match range.split_once("..") {
+        Some(ends) => ends,
+        None => return Err("expected a range like 5..10".to_string()),
+    };
+
+    let parse_end = |end: &str, default: usize| match end.trim() {
+        "" => Ok(default),
+        end => end
+            .parse::<usize>()
+            .map_err(|_| format!("invalid row number: {}", end)),
+    };
+
+    let range = RowRange {
+        first: parse_end(first, 1)?,
+        last: parse_end(last, usize::MAX)?,
+    };
+
+    if range.first == 0 {
+        return Err("rows are counted from 1".to_string());
+    }
+
+    if range.last < range.first {
+        return Err(format!(
+            "the range ends at row {}, before it starts",
+            range.last
+        ));
+    }
+
+    Ok(range)
+}
+
+// The subcommands, for doing something other than converting a file. (This is a plain comment,
+// because a doc comment here would replace the program's `about`.)
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print the metadata from a native file's header, without needing a types file
+    Inspect(InspectArgs),
+    /// Compile an annotated text description of a native file into the file itself
+    Assemble(AssembleArgs),
+    /// Count the rows in a native file, without decoding them
+    Count(CountArgs),
+    /// Compare two native files with the same column types, and report the rows that differ
+    Diff(DiffArgs),
+    /// Print an annotated hex dump of a native file's header and rows
+    Explain(ExplainArgs),
+    /// Record where every Nth row of a native file starts, so rows can be skipped quickly
+    Index(IndexArgs),
+    /// Report statistics about each column of a native file, like null counts and value ranges
+    Profile(ProfileArgs),
+    /// Write a native file of random values, of the types in a types file
+    Generate(GenerateArgs),
+    /// Check that a native file is well-formed, from the signature to the end
+    Validate(ValidateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct InspectArgs {
+    /// The file to inspect
+    #[arg()]
+    pub input: String,
+
+    /// Output in JSON format [default: text]
+    #[arg(short = 'j', long = "json")]
+    pub is_json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AssembleArgs {
+    /// The source file to assemble
+    #[arg()]
+    pub input: String,
+
+    /// Output file name; use - for stdout
+    #[arg(short, long)]
+    pub output: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CountArgs {
+    /// The file to count the rows of
+    #[arg()]
+    pub input: String,
+
+    /// Also count the nulls in each column
+    #[arg(short, long)]
+    pub nulls: bool,
+
+    /// File with list of column types and names, used to name the columns in the null counts
+    #[arg(short, long)]
+    pub types: Option<String>,
+
+    /// Output in JSON format [default: text]
+    #[arg(short = 'j', long = "json")]
+    pub is_json: bool,
+
+    /// Memory-map the input file, instead of reading it through a buffer
+    #[arg(long)]
+    pub mmap: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// The original file
+    #[arg()]
+    pub first: String,
+
+    /// The file to compare it with
+    #[arg()]
+    pub second: String,
+
+    /// File with list of column types, names, and conversions, shared by both files
+    #[arg(short, long)]
+    pub types: String,
+
+    /// Match rows by these columns, by name or number, instead of by position
+    #[arg(short, long, value_delimiter = ',')]
+    pub key: Vec<String>,
+
+    /// How many differing rows to show; they're all counted
+    #[arg(short, long, default_value_t = 10)]
+    pub max_differences: usize,
+
+    /// Output in JSON format [default: text]
+    #[arg(short = 'j', long = "json")]
+    pub is_json: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExplainArgs {
+    /// The file to explain
+    #[arg()]
+    pub input: String,
+
+    /// File with list of column types and names, used to show each column's type and value
+    #[arg(short, long)]
+    pub types: Option<String>,
+
+    /// Only explain these rows, counting from 1 [default: all rows]
+    #[arg(short, long, value_delimiter = ',')]
+    pub rows: Vec<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct GenerateArgs {
+    /// File with list of column types, and optional names and conversions
+    #[arg()]
+    pub types: String,
+
+    /// Output file name; use - for stdout
+    #[arg(short, long)]
+    pub output: String,
+
+    /// The number of rows to generate
+    #[arg(short, long, default_value_t = 100)]
+    pub rows: usize,
+
+    /// The chance, from 0 to 1, of each value being null
+    #[arg(short, long, default_value_t = 0.0)]
+    pub null_probability: f64,
+
+    /// Seed for the random values; the same seed always generates the same file
+    #[arg(short, long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct IndexArgs {
+    /// The file to index; the index is written next to it, with .idx added to its name
+    #[arg()]
+    pub input: String,
+
+    /// Record the offset of every <INTERVAL>th row
+    #[arg(short, long, default_value_t = 1000)]
+    pub interval: usize,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ProfileArgs {
+    /// The file to profile
+    #[arg()]
+    pub input: String,
+
+    /// File with list of column types, names, and conversions
+    #[arg(short, long)]
+    pub types: String,
+
+    /// How many of the most frequent values to show for each column
+    #[arg(short = 'k', long, default_value_t = 5)]
+    pub top: usize,
+
+    /// Output in JSON format [default: text]
+    #[arg(short = 'j', long = "json")]
+    pub is_json: bool,
+
+    /// Memory-map the input file, instead of reading it through a buffer
+    #[arg(long)]
+    pub mmap: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+    /// The file to validate
+    #[arg()]
+    pub input: String,
+}
+
+/// What the command line asked for; either converting a file, or running a subcommand.
+#[derive(Debug)]
+pub enum Invocation {
+    /// Boxed, because `Args` is much bigger than any subcommand's arguments
+    Convert(Box<Args>),
+    Command(Command),
+}
+
+impl Invocation {
+    /// Parse the command line, exiting with a usage message if it's not valid.
+    ///
+    /// The subcommands are added to the `Args` parser, rather than being a field of it, because
+    /// `Args` requires an input file and a types file, which the subcommands don't want.
+    pub fn parse() -> Self {
+        let matches = Command::augment_subcommands(Args::command())
+            .args_conflicts_with_subcommands(true)
+            .subcommand_negates_reqs(true)
+            .get_matches();
+
+        let invocation = if matches.subcommand().is_some() {
+            Command::from_arg_matches(&matches).map(Invocation::Command)
+        } else {
+            Args::from_arg_matches(&matches).map(|args| Invocation::Convert(Box::new(args)))
+        };
+
+        invocation.unwrap_or_else(|e| e.exit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::args::{parse_float_format, parse_row_range, BoolFormat, FloatFormat, RowRange};
+
+    #[test]
+    fn test_parse_row_range() {
+        assert_eq!(
+            parse_row_range("5..10"),
+            Ok(RowRange { first: 5, last: 10 })
+        );
+        assert_eq!(
+            parse_row_range("5.."),
+            Ok(RowRange {
+                first: 5,
+                last: usize::MAX
+            })
+        );
+        assert_eq!(parse_row_range("..10"), Ok(RowRange { first: 1, last: 10 }));
+        assert_eq!(parse_row_range("7..7"), Ok(RowRange { first: 7, last: 7 }));
+
+        assert!(parse_row_range("5").is_err());
+        assert!(parse_row_range("0..5").is_err());
+        assert!(parse_row_range("10..5").is_err());
+        assert!(parse_row_range("a..5").is_err());
+    }
+
+    #[test]
+    fn test_parse_bool_format() {
+        assert_eq!("1/0".parse(), Ok(BoolFormat::Digits));
+        assert_eq!("t/f".parse(), Ok(BoolFormat::Letters));
+        assert_eq!(
+            "TRUE/FALSE".parse::<BoolFormat>().map(|f| f.text(false)),
+            Ok("FALSE")
+        );
+
+        assert!("yes/no".parse::<BoolFormat>().is_err());
+        assert!("True/False".parse::<BoolFormat>().is_err());
+    }
+
+    #[test]
+    fn test_parse_float_format() {
+        assert_eq!(parse_float_format("shortest"), Ok(FloatFormat::Shortest));
+        assert_eq!(parse_float_format("fixed:3"), Ok(FloatFormat::Fixed(3)));
+        assert_eq!(parse_float_format("fixed:0"), Ok(FloatFormat::Fixed(0)));
+        assert_eq!(
+            parse_float_format("scientific"),
+            Ok(FloatFormat::Scientific)
+        );
+
+        assert!(parse_float_format("fixed").is_err());
+        assert!(parse_float_format("fixed:").is_err());
+        assert!(parse_float_format("fixed:-1").is_err());
+        assert!(parse_float_format("exponential").is_err());
+    }
+}
@@ -0,0 +1,613 @@
+use std::fs::{self, File};
+use std::io::{stdout, Write};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use uuid::Uuid;
+
+use crate::args::AssembleArgs;
+use crate::column_type::{INFINITY, NEGATIVE_INFINITY};
+use crate::file_signature::VALID_FILE_SIGNATURE_BYTES;
+use crate::vertica_native_file::bitfield_length;
+
+/// Run the `assemble` subcommand, compiling the source file into a native file.
+///
+/// * `args` - the subcommand's arguments
+pub fn assemble_file(args: &AssembleArgs) -> anyhow::Result<()> {
+    let source = match fs::read_to_string(&args.input) {
+        Ok(source) => source,
+        Err(e) => bail!("opening input file [{}]: {}", args.input, e),
+    };
+
+    let bytes = assemble(&source)?;
+
+    if args.output == "-" {
+        stdout().lock().write_all(&bytes)?;
+    } else {
+        File::create(&args.output)
+            .and_then(|mut file| file.write_all(&bytes))
+            .with_context(|| format!("writing output file [{}]", args.output))?;
+    }
+
+    Ok(())
+}
+
+/// Compile an annotated description of a native file into the file's bytes. The source is made
+/// up of whitespace-separated items, and anything after a `#` is a comment. Each item is one of:
+///
+/// * raw hex bytes, like `4e 41` or `4e41`
+/// * a typed literal, like `int64:-1`, `date:1999-01-08` or `varchar:"one"`; see
+///   `assemble_literal` for the full list
+/// * a directive, which has to be the first thing on its line:
+///   * `@signature` - the signature every native file starts with
+///   * `@header 8 8 10 var` - the rest of the header, for columns of the given widths, with
+///     `var` for variable-width columns
+///   * `@row` - the start of a row; the row's length is filled in from whatever follows it, up
+///     to the next `@row`, or the end of the source
+///   * `@nulls 2 5` - a row's null bitfield, with the given columns, counting from 1, null
+///
+/// `@row` and `@nulls` need to know how many columns there are, so they need an `@header` before
+/// them. Everything else can be used anywhere, to build files that are broken on purpose.
+pub fn assemble(source: &str) -> anyhow::Result<Vec<u8>> {
+    let mut assembler = Assembler {
+        bytes: vec![],
+        column_count: None,
+        row_start: None,
+    };
+
+    for (index, line) in source.lines().enumerate() {
+        assembler
+            .assemble_line(line)
+            .map_err(|e| anyhow!("line {}: {}", index + 1, e))?;
+    }
+
+    assembler.finish_row()?;
+
+    Ok(assembler.bytes)
+}
+
+struct Assembler {
+    /// The bytes assembled so far
+    bytes: Vec<u8>,
+    /// The number of columns, from the last `@header`
+    column_count: Option<usize>,
+    /// Where the length of the current row goes, if a row has been started
+    row_start: Option<usize>,
+}
+
+impl Assembler {
+    fn assemble_line(&mut self, line: &str) -> anyhow::Result<()> {
+        let tokens = tokenize(line)?;
+
+        let (first, rest) = match tokens.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        match first.as_str() {
+            "@signature" => self.bytes.extend_from_slice(&VALID_FILE_SIGNATURE_BYTES),
+            "@header" => self.assemble_header(rest)?,
+            "@row" => {
+                self.finish_row()?;
+                self.column_count()?;
+                self.row_start = Some(self.bytes.len());
+                self.bytes.extend_from_slice(&[0; 4]);
+            }
+            "@nulls" => {
+                let mut bitfield = vec![0; bitfield_length(self.column_count()?)];
+
+                for column in rest {
+                    let index = match column.parse::<usize>() {
+                        Ok(column) if column >= 1 && column <= self.column_count()? => column - 1,
+                        _ => bail!("invalid column for @nulls: {}", column),
+                    };
+
+                    bitfield[index / 8] |= 0x80 >> (index % 8);
+                }
+
+                self.bytes.extend_from_slice(&bitfield);
+            }
+            directive if directive.starts_with('@') => bail!("unknown directive: {}", directive),
+            _ => {
+                for token in &tokens {
+                    self.assemble_token(token)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Everything in the header after the signature. The header length is worked out from the
+    /// number of columns.
+    fn assemble_header(&mut self, widths: &[String]) -> anyhow::Result<()> {
+        let header_length = 2 + 1 + 2 + 4 * widths.len() as u32;
+
+        self.bytes.extend_from_slice(&header_length.to_le_bytes());
+        // The version, and the filler
+        self.bytes.extend_from_slice(&[1, 0, 0]);
+        self.bytes
+            .extend_from_slice(&(widths.len() as u16).to_le_bytes());
+
+        for width in widths {
+            let width = match width.as_str() {
+                "var" => u32::MAX,
+                width => match width.parse::<u32>() {
+                    Ok(width) => width,
+                    Err(_) => bail!("invalid column width: {}", width),
+                },
+            };
+
+            self.bytes.extend_from_slice(&width.to_le_bytes());
+        }
+
+        self.column_count = Some(widths.len());
+
+        Ok(())
+    }
+
+    /// Fill in the length of the current row, if there is one. Like in the file, the length
+    /// doesn't include the null bitfield.
+    fn finish_row(&mut self) -> anyhow::Result<()> {
+        let start = match self.row_start.take() {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+
+        let bitfield_length = bitfield_length(self.column_count()?);
+        let length = self.bytes.len() - (start + 4);
+
+        if length < bitfield_length {
+            bail!("row is shorter than its null bitfield");
+        }
+
+        let row_length = (length - bitfield_length) as u32;
+        self.bytes[start..start + 4].copy_from_slice(&row_length.to_le_bytes());
+
+        Ok(())
+    }
+
+    fn column_count(&self) -> anyhow::Result<usize> {
+        match self.column_count {
+            Some(count) => Ok(count),
+            None => bail!("@row and @nulls need an @header before them"),
+        }
+    }
+
+    fn assemble_token(&mut self, token: &str) -> anyhow::Result<()> {
+        match token.split_once(':') {
+            Some((kind, value)) => assemble_literal(kind, &unquote(value)?, &mut self.bytes)
+                .map_err(|e| anyhow!("{}: {}", token, e)),
+            None => assemble_hex(token, &mut self.bytes),
+        }
+    }
+}
+
+/// Split a line into its items, dropping any comment. Items are separated by whitespace, except
+/// inside double quotes, where the quotes are kept, for `unquote` to deal with.
+fn tokenize(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens: Vec<String> = vec![];
+    let mut token = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                token.push(c);
+            }
+            '\\' if in_quotes => {
+                token.push(c);
+
+                if let Some(escaped) = chars.next() {
+                    token.push(escaped);
+                }
+            }
+            '#' if !in_quotes => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if in_quotes {
+        bail!("unterminated string");
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Strip the quotes from a literal's value, if it has them, and deal with `\"` and `\\`.
+fn unquote(value: &str) -> anyhow::Result<String> {
+    if !value.starts_with('"') {
+        return Ok(value.to_string());
+    }
+
+    if value.len() < 2 || !value.ends_with('"') {
+        bail!("invalid string: {}", value);
+    }
+
+    let mut unquoted = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('"' | '\\')) => unquoted.push(escaped),
+                _ => bail!("invalid escape in string: {}", value),
+            },
+            c => unquoted.push(c),
+        }
+    }
+
+    Ok(unquoted)
+}
+
+/// Raw bytes, as pairs of hex digits
+fn assemble_hex(token: &str, bytes: &mut Vec<u8>) -> anyhow::Result<()> {
+    bytes.extend_from_slice(&parse_hex(token)?);
+
+    Ok(())
+}
+
+fn parse_hex(hex: &str) -> anyhow::Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("invalid hex bytes: {}", hex);
+    }
+
+    Ok((0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+/// Assemble a typed literal, encoded the way the native format stores that type.
+///
+/// * `int8`, `int16`, `int32`, `int64` - signed integers
+/// * `uint8`, `uint16`, `uint32`, `uint64` - unsigned integers, handy for lengths
+/// * `float64` - a `float`
+/// * `bool` - `true` or `false`
+/// * `date` - `1999-01-08`
+/// * `timestamp`, `timestamptz` - `"1999-02-23 03:11:52.35"`; a `timestamptz` is in UTC
+/// * `time` - `07:09:23`
+/// * `timetz` - `15:12:34-05`, or with the offset's minutes, `15:12:34+05:30`
+/// * `interval` - `03:03:03`, with optional fractional seconds, and as many hours as needed,
+///   or with days in front, like `"2 03:03:03"`; an `interval year to month` is an `int64` of
+///   months
+/// * `uuid` - `a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8`
+/// * `char`, `varchar` - a string, in double quotes if it has spaces; a `char(10)` is padded
+///   with spaces to 10 bytes, and a `varchar` has its length before it
+/// * `binary`, `varbinary` - hex bytes; a `binary(3)` is padded with zeroes to 3 bytes, and a
+///   `varbinary` has its length before it
+/// * `bits` - bytes given as binary digits, like `01000000`, for null bitfields
+///
+/// * `kind` - the type of the literal, with its optional width
+/// * `value` - the literal's value, without any quotes
+/// * `bytes` - where to put the encoded value
+fn assemble_literal(kind: &str, value: &str, bytes: &mut Vec<u8>) -> anyhow::Result<()> {
+    let (kind, width) = match kind.split_once('(') {
+        Some((kind, width)) => match width.strip_suffix(')').map(|w| w.parse::<usize>()) {
+            Some(Ok(width)) => (kind, Some(width)),
+            _ => bail!("invalid width"),
+        },
+        None => (kind, None),
+    };
+
+    if width.is_some() && kind != "char" && kind != "binary" {
+        bail!("only char and binary can have a width");
+    }
+
+    match kind {
+        "int8" => bytes.extend_from_slice(&value.parse::<i8>()?.to_le_bytes()),
+        "int16" => bytes.extend_from_slice(&value.parse::<i16>()?.to_le_bytes()),
+        "int32" => bytes.extend_from_slice(&value.parse::<i32>()?.to_le_bytes()),
+        "int64" => bytes.extend_from_slice(&value.parse::<i64>()?.to_le_bytes()),
+        "uint8" => bytes.extend_from_slice(&value.parse::<u8>()?.to_le_bytes()),
+        "uint16" => bytes.extend_from_slice(&value.parse::<u16>()?.to_le_bytes()),
+        "uint32" => bytes.extend_from_slice(&value.parse::<u32>()?.to_le_bytes()),
+        "uint64" => bytes.extend_from_slice(&value.parse::<u64>()?.to_le_bytes()),
+        "float64" => bytes.extend_from_slice(&value.parse::<f64>()?.to_le_bytes()),
+        "bool" => bytes.push(value.parse::<bool>()? as u8),
+        "date" | "timestamp" | "timestamptz" if value == "infinity" => {
+            bytes.extend_from_slice(&INFINITY.to_le_bytes())
+        }
+        "date" | "timestamp" | "timestamptz" if value == "-infinity" => {
+            bytes.extend_from_slice(&NEGATIVE_INFINITY.to_le_bytes())
+        }
+        "date" => {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+            bytes.extend_from_slice(&encode_date(date).to_le_bytes());
+        }
+        "timestamp" | "timestamptz" => {
+            let timestamp = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")?;
+            bytes.extend_from_slice(&encode_timestamp(timestamp).to_le_bytes());
+        }
+        "time" => {
+            let time = NaiveTime::parse_from_str(value, "%H:%M:%S%.f")?;
+            bytes.extend_from_slice(&encode_time(time).to_le_bytes());
+        }
+        "timetz" => {
+            // The offset starts at the last sign in the value.
+            let split = match value.rfind(['+', '-']) {
+                Some(split) => split,
+                None => bail!("missing time zone offset"),
+            };
+
+            let time = NaiveTime::parse_from_str(&value[..split], "%H:%M:%S%.f")?;
+            let offset = parse_offset(&value[split..])?;
+
+            bytes.extend_from_slice(&encode_time_tz(time, offset).to_le_bytes());
+        }
+        "interval" => bytes.extend_from_slice(&parse_interval(value)?.to_le_bytes()),
+        "uuid" => bytes.extend_from_slice(Uuid::parse_str(value)?.as_bytes()),
+        "char" => bytes.extend_from_slice(&pad(value.as_bytes(), width, b' ')?),
+        "varchar" => {
+            bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        "binary" => bytes.extend_from_slice(&pad(&parse_hex(value)?, width, 0)?),
+        "varbinary" => {
+            let value = parse_hex(value)?;
+            bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&value);
+        }
+        "bits" => {
+            if !value.len().is_multiple_of(8) || !value.chars().all(|c| c == '0' || c == '1') {
+                bail!("bits need to be a multiple of 8 binary digits");
+            }
+
+            for i in (0..value.len()).step_by(8) {
+                bytes.push(u8::from_str_radix(&value[i..i + 8], 2)?);
+            }
+        }
+        _ => bail!("unknown type"),
+    }
+
+    Ok(())
+}
+
+/// Pad `value` out to `width` bytes, if there is a width.
+fn pad(value: &[u8], width: Option<usize>, padding: u8) -> anyhow::Result<Vec<u8>> {
+    let mut value = value.to_vec();
+
+    if let Some(width) = width {
+        if value.len() > width {
+            bail!("value is longer than {} bytes", width);
+        }
+
+        value.resize(width, padding);
+    }
+
+    Ok(value)
+}
+
+/// Parse a time zone offset, like `-05`, or `+05:30`, into seconds east of UTC.
+fn parse_offset(offset: &str) -> anyhow::Result<i32> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let mut parts = offset[1..].splitn(2, ':');
+
+    let hours: i32 = parts.next().unwrap_or("").parse()?;
+    let minutes: i32 = parts.next().unwrap_or("0").parse()?;
+
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse an interval, like `03:03:03`, `-100:00:00.5`, or `4 04:00:00`, into microseconds.
+fn parse_interval(interval: &str) -> anyhow::Result<i64> {
+    let (sign, interval) = match interval.strip_prefix('-') {
+        Some(interval) => (-1, interval),
+        None => (1, interval),
+    };
+
+    let (days, interval) = match interval.split_once(' ') {
+        Some((days, interval)) => (days.parse::<i64>()?, interval.trim_start()),
+        None => (0, interval),
+    };
+
+    let parts: Vec<&str> = interval.split(':').collect();
+
+    if parts.len() != 3 {
+        bail!("intervals look like HH:MM:SS");
+    }
+
+    let (seconds, fraction) = parts[2].split_once('.').unwrap_or((parts[2], ""));
+
+    if fraction.len() > 6 {
+        bail!("intervals only go down to microseconds");
+    }
+
+    let hours: i64 = days * 24 + parts[0].parse::<i64>()?;
+    let minutes: i64 = parts[1].parse()?;
+    let seconds: i64 = seconds.parse()?;
+    let micros: i64 = format!("{:0<6}", fraction).parse()?;
+
+    Ok(sign * (((hours * 60 + minutes) * 60 + seconds) * 1_000_000 + micros))
+}
+
+/// The Vertica epoch, which dates and timestamps count from
+fn vertica_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0)
+}
+
+/// A date, as a number of days since the Vertica epoch
+pub fn encode_date(date: NaiveDate) -> i64 {
+    date.signed_duration_since(vertica_epoch().date())
+        .num_days()
+}
+
+/// A timestamp, as a number of microseconds since the Vertica epoch
+pub fn encode_timestamp(timestamp: NaiveDateTime) -> i64 {
+    timestamp
+        .signed_duration_since(vertica_epoch())
+        .num_microseconds()
+        .unwrap()
+}
+
+/// A time, as a number of microseconds since midnight
+pub fn encode_time(time: NaiveTime) -> i64 {
+    time.signed_duration_since(NaiveTime::from_hms(0, 0, 0))
+        .num_microseconds()
+        .unwrap()
+}
+
+/// A time with a time zone. The time is stored in UTC, as microseconds since midnight, in the
+/// top 40 bits. The bottom 24 bits hold the offset, in seconds west of UTC, plus 24 hours, to
+/// keep it positive.
+///
+/// * `time` - the local time
+/// * `offset` - the time zone's offset, in seconds east of UTC
+pub fn encode_time_tz(time: NaiveTime, offset: i32) -> u64 {
+    let day = 24 * 3600 * 1_000_000;
+    let utc = (encode_time(time) - offset as i64 * 1_000_000).rem_euclid(day);
+
+    ((utc as u64) << 24) | (24 * 3600 - offset) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::assemble::{assemble, tokenize};
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"4e 41 varchar:"one # two" char:x  # comment"#).unwrap(),
+            vec!["4e", "41", r#"varchar:"one # two""#, "char:x"]
+        );
+        assert_eq!(
+            tokenize(r#"varchar:"say \"hi\"""#).unwrap(),
+            vec![r#"varchar:"say \"hi\"""#]
+        );
+        assert!(tokenize(r#"varchar:"one"#).is_err());
+    }
+
+    #[test]
+    fn test_literals() {
+        let cases: Vec<(&str, Vec<u8>)> = vec![
+            ("4e41 0a", vec![0x4e, 0x41, 0x0a]),
+            ("int64:-1", vec![0xff; 8]),
+            ("int8:5 int16:-2", vec![5, 0xfe, 0xff]),
+            ("uint32:3", vec![3, 0, 0, 0]),
+            ("bool:true bool:false", vec![1, 0]),
+            (
+                "date:1999-01-08",
+                vec![0x9a, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+            (
+                r#"timestamp:"1999-02-23 03:11:52.35""#,
+                vec![0x30, 0x85, 0xb3, 0x4f, 0x7e, 0xe7, 0xff, 0xff],
+            ),
+            ("date:infinity", i64::MAX.to_le_bytes().to_vec()),
+            (
+                "timestamptz:-infinity",
+                (i64::MIN + 1).to_le_bytes().to_vec(),
+            ),
+            ("time:07:09:23", vec![0xc0, 0x2e, 0x98, 0xff, 0x05, 0, 0, 0]),
+            (
+                "timetz:15:12:34-05",
+                vec![0xd0, 0x97, 0x01, 0x80, 0xf0, 0x79, 0xf0, 0x10],
+            ),
+            (
+                "interval:03:03:03",
+                vec![0xc0, 0x47, 0xa3, 0x8e, 0x02, 0, 0, 0],
+            ),
+            (
+                r#"interval:"-1 00:00:00.5""#,
+                (-86_400_500_000i64).to_le_bytes().to_vec(),
+            ),
+            ("char(5):one", b"one  ".to_vec()),
+            (r#"char:"a b""#, b"a b".to_vec()),
+            (r#"varchar:"ONE""#, vec![3, 0, 0, 0, b'O', b'N', b'E']),
+            ("binary(3):abcd", vec![0xab, 0xcd, 0]),
+            ("varbinary:abcd", vec![2, 0, 0, 0, 0xab, 0xcd]),
+            ("bits:0100000010000000", vec![0x40, 0x80]),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(assemble(source).unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_directives() {
+        let source = "
+            @signature
+            @header 8 var
+            @row
+            @nulls 2
+            int64:1
+            @row
+            @nulls
+            int64:2 varchar:two
+        ";
+
+        let mut expected: Vec<u8> = vec![];
+        expected.extend_from_slice(&[
+            0x4e, 0x41, 0x54, 0x49, 0x56, 0x45, 0x0a, 0xff, 0x0d, 0x0a, 0,
+        ]);
+        expected.extend_from_slice(&[13, 0, 0, 0, 1, 0, 0, 2, 0]);
+        expected.extend_from_slice(&[8, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        expected.extend_from_slice(&[8, 0, 0, 0, 0x40, 1, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[15, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[3, 0, 0, 0, b't', b'w', b'o']);
+
+        assert_eq!(assemble(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_fixtures() {
+        for (source, binary) in &[
+            ("source-data/all-types.asm", "data/all-types.bin"),
+            (
+                "source-data/all-types-with-nulls.asm",
+                "data/all-types-with-nulls.bin",
+            ),
+        ] {
+            let source = fs::read_to_string(source).unwrap();
+
+            assert_eq!(assemble(&source).unwrap(), fs::read(binary).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = vec![
+            ("4e4", "line 1: invalid hex bytes: 4e4"),
+            (
+                "int8:300",
+                "line 1: int8:300: number too large to fit in target type",
+            ),
+            (
+                "\n@row",
+                "line 2: @row and @nulls need an @header before them",
+            ),
+            (
+                "@header 8\n@nulls 2",
+                "line 2: invalid column for @nulls: 2",
+            ),
+            ("@bogus", "line 1: unknown directive: @bogus"),
+            (
+                "int(3):1",
+                "line 1: int(3):1: only char and binary can have a width",
+            ),
+            (
+                "char(2):abc",
+                "line 1: char(2):abc: value is longer than 2 bytes",
+            ),
+            ("float32:1", "line 1: float32:1: unknown type"),
+        ];
+
+        for (source, message) in cases {
+            assert_eq!(assemble(source).unwrap_err().to_string(), message);
+        }
+    }
+}
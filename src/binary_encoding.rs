@@ -0,0 +1,128 @@
+use std::fmt::{self, Write};
+
+/// How `Binary` and `Varbinary` values are written out, chosen with `--binary-encoding`.
+// The variants have plain comments, because doc comments would turn `--help` into the long form.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BinaryEncoding {
+    // Two uppercase hex digits for each byte
+    Hex,
+    // Standard base64, with padding
+    Base64,
+    // Printable ASCII as it is, and other bytes as \ooo, the way Vertica shows VARBINARY values
+    OctalEscape,
+    // The bytes themselves, in CSV only
+    Raw,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl BinaryEncoding {
+    /// Write `bytes` to `out` in this encoding. Every byte is kept, including zeros, except with
+    /// `Raw`, which only has text to write to here, so bytes that aren't valid UTF-8 are replaced
+    /// with `U+FFFD`. CSV output writes raw values straight from the row instead, so it has all
+    /// of them.
+    ///
+    /// * `out` - where to write the encoded bytes
+    /// * `bytes` - the bytes to encode
+    /// * `hex_prefix` - whether to start hex with `0x`; the other encodings ignore it
+    pub fn write(&self, out: &mut impl Write, bytes: &[u8], hex_prefix: bool) -> fmt::Result {
+        match self {
+            BinaryEncoding::Hex => {
+                if hex_prefix {
+                    out.write_str("0x")?;
+                }
+
+                for b in bytes {
+                    write!(out, "{:02X}", b)?;
+                }
+
+                Ok(())
+            }
+            BinaryEncoding::Base64 => {
+                for chunk in bytes.chunks(3) {
+                    let group = chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u32, |group, (i, b)| group | (*b as u32) << (16 - 8 * i));
+
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            let sextet = (group >> (18 - 6 * i)) & 0x3F;
+                            out.write_char(BASE64_ALPHABET[sextet as usize] as char)?;
+                        } else {
+                            out.write_char('=')?;
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            BinaryEncoding::OctalEscape => {
+                for b in bytes {
+                    match b {
+                        b'\\' => out.write_str("\\\\")?,
+                        0x20..=0x7E => out.write_char(*b as char)?,
+                        _ => write!(out, "\\{:03o}", b)?,
+                    }
+                }
+
+                Ok(())
+            }
+            BinaryEncoding::Raw => out.write_str(&String::from_utf8_lossy(bytes)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary_encoding::BinaryEncoding;
+
+    fn encode(encoding: BinaryEncoding, bytes: &[u8]) -> String {
+        let mut output = String::new();
+        encoding.write(&mut output, bytes, false).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(encode(BinaryEncoding::Hex, &[0x0A, 0x0B]), "0A0B");
+        assert_eq!(encode(BinaryEncoding::Hex, &[0xAB]), "AB");
+        assert_eq!(encode(BinaryEncoding::Hex, &[0x00, 0x01, 0x00]), "000100");
+        assert_eq!(encode(BinaryEncoding::Hex, &[]), "");
+
+        let mut output = String::new();
+        BinaryEncoding::Hex
+            .write(&mut output, &[0x1F], true)
+            .unwrap();
+        assert_eq!(output, "0x1F");
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(encode(BinaryEncoding::Base64, b""), "");
+        assert_eq!(encode(BinaryEncoding::Base64, b"f"), "Zg==");
+        assert_eq!(encode(BinaryEncoding::Base64, b"fo"), "Zm8=");
+        assert_eq!(encode(BinaryEncoding::Base64, b"foo"), "Zm9v");
+        assert_eq!(encode(BinaryEncoding::Base64, b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(BinaryEncoding::Base64, &[0x00, 0xFF, 0xFE]), "AP/+");
+    }
+
+    #[test]
+    fn test_octal_escape() {
+        assert_eq!(encode(BinaryEncoding::OctalEscape, b"abc"), "abc");
+        assert_eq!(
+            encode(
+                BinaryEncoding::OctalEscape,
+                &[b'a', 0x00, b'\\', 0x7F, 0xFF, b' ']
+            ),
+            "a\\000\\\\\\177\\377 "
+        );
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(encode(BinaryEncoding::Raw, b"one\0two"), "one\0two");
+        assert_eq!(encode(BinaryEncoding::Raw, &[b'a', 0xFF]), "a\u{FFFD}");
+    }
+}
@@ -2,12 +2,20 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use anyhow::bail;
 
+use crate::args::BoolFormat;
+use crate::time_format::TimeFormat;
+
 #[derive(Debug)]
-/// Conversion functions for IP and Mac addresses. Additional conversations may be added later.
+/// Conversion functions for IP and Mac addresses, and formats for dates, times, and booleans.
+/// Additional conversations may be added later.
 ///
 pub enum ColumnConversion {
     IpAddress,
     MacAddress,
+    /// A format for a date or time column, like `epoch_ms`, or `%m/%d/%Y`
+    Format(TimeFormat),
+    /// A format for a boolean column, like `t/f`
+    BoolFormat(BoolFormat),
 }
 
 impl ColumnConversion {
@@ -21,61 +29,45 @@ impl ColumnConversion {
         Ok(result)
     }
 
-    /// Convert the vector of bytes into a formatted string, according to the type of `self`.
+    /// Convert the slice of bytes into a formatted string, according to the type of `self`.
+    /// Every byte is used, including zeros; only the zeros padding the end of a `binary(n)`
+    /// value, past the end of the address, are ignored.
     ///
-    /// * `bytes` - the vector of bytes to convert
+    /// * `bytes` - the bytes to convert
     ///
-    pub fn convert(&self, bytes: Vec<u8>) -> String {
-        match &*self {
+    pub fn convert(&self, bytes: &[u8]) -> String {
+        match self {
             ColumnConversion::IpAddress => {
-                if bytes[0] == 0xff && bytes[1] == 0xff {
-                    let tmp: Vec<String> =
-                        bytes[2..].iter().map(|b| format!("{:0>2X}", b)).collect();
-
-                    if tmp.len() == 0 {
+                // An IPv4 address is marked with two 0xFF bytes before it.
+                if let [0xff, 0xff, address @ ..] = bytes {
+                    if address.is_empty() {
                         return "".to_string();
                     }
 
-                    let addr = u32::from_str_radix(&tmp.join(""), 16).map(Ipv4Addr::from);
+                    let mut octets = [0u8; 4];
+                    octets.iter_mut().zip(address).for_each(|(o, b)| *o = *b);
 
-                    match addr {
-                        Err(e) => {
-                            eprintln!("error converting to IP address: {}", e);
-                            "".to_string()
-                        }
-                        Ok(s) => s.to_string(),
-                    }
+                    Ipv4Addr::from(octets).to_string()
                 } else {
-                    let tmp_bytes = if bytes.len() < 16 {
-                        let mut tmp_bytes: Vec<u8> = vec![0; 16];
-
-                        for (index, b) in bytes.iter().enumerate() {
-                            tmp_bytes[index] = *b;
-                        }
-
-                        tmp_bytes
-                    } else {
-                        bytes
-                    };
-
-                    let tmp: Vec<String> =
-                        tmp_bytes.iter().map(|b| format!("{:0>2X}", b)).collect();
-                    match u128::from_str_radix(&tmp.join(""), 16) {
-                        Err(e) => {
-                            eprintln!("error converting to IP address: {}", e);
-                            "".to_string()
-                        }
-                        Ok(s) => {
-                            let addr: IpAddr = Ipv6Addr::from(s).into();
-                            addr.to_string()
-                        }
-                    }
+                    let mut octets = [0u8; 16];
+                    octets.iter_mut().zip(bytes).for_each(|(o, b)| *o = *b);
+
+                    let addr: IpAddr = Ipv6Addr::from(octets).into();
+                    addr.to_string()
                 }
             }
             ColumnConversion::MacAddress => {
+                // Zeros after the sixth byte are padding, unless it's a longer, EUI-64, address.
+                let end = bytes.iter().rposition(|b| *b != 0x00).map_or(0, |i| i + 1);
+                let bytes = &bytes[..end.max(bytes.len().min(6))];
+
                 let addr: Vec<String> = bytes.iter().map(|b| format!("{:0>2X}", b)).collect();
                 addr.join(":")
             }
+            // Formats are only for dates, times, and booleans, and don't convert bytes.
+            ColumnConversion::Format(_) | ColumnConversion::BoolFormat(_) => {
+                bytes.iter().map(|b| format!("{:02X}", b)).collect()
+            }
         }
     }
 }
@@ -89,7 +81,7 @@ mod tests {
         let bytes = vec![0xFF, 0xFF];
 
         let cnv = ColumnConversion::IpAddress;
-        let val = cnv.convert(bytes);
+        let val = cnv.convert(&bytes);
 
         assert_eq!("", val);
     }
@@ -99,11 +91,40 @@ mod tests {
         let bytes = vec![0xFFu8, 0xFFu8, 0xC0u8, 0xA8u8, 0xBu8, 0x2u8];
 
         let cnv = ColumnConversion::IpAddress;
-        let val = cnv.convert(bytes);
+        let val = cnv.convert(&bytes);
 
         assert_eq!("192.168.11.2", val);
     }
 
+    #[test]
+    fn test_ip_v4_with_zero_bytes() {
+        let cnv = ColumnConversion::IpAddress;
+
+        assert_eq!(
+            "10.0.0.1",
+            cnv.convert(&[0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x01])
+        );
+        assert_eq!(
+            "10.0.0.0",
+            cnv.convert(&[0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x00])
+        );
+
+        // A binary(16), padded with zeros
+        let mut padded = vec![0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x01];
+        padded.resize(16, 0x00);
+        assert_eq!("10.0.0.1", cnv.convert(&padded));
+    }
+
+    #[test]
+    fn test_ip_v6_with_zero_bytes() {
+        let mut bytes = [0u8; 16];
+        bytes[15] = 0x01;
+
+        let cnv = ColumnConversion::IpAddress;
+
+        assert_eq!("::1", cnv.convert(&bytes));
+    }
+
     #[test]
     fn test_ip_v6() {
         let bytes = vec![
@@ -111,7 +132,7 @@ mod tests {
         ];
 
         let cnv = ColumnConversion::IpAddress;
-        let val = cnv.convert(bytes);
+        let val = cnv.convert(&bytes);
 
         assert_eq!("2001:402:423:fffe:9ef1:6e00::", val);
     }
@@ -121,8 +142,22 @@ mod tests {
         let bytes = vec![0xF4u8, 0xF, 0x1B, 0x28, 0xF2, 0x4C];
 
         let cnv = ColumnConversion::MacAddress;
-        let val = cnv.convert(bytes);
+        let val = cnv.convert(&bytes);
 
         assert_eq!("F4:0F:1B:28:F2:4C", val);
     }
+
+    #[test]
+    fn test_mac_with_zero_bytes() {
+        let cnv = ColumnConversion::MacAddress;
+
+        assert_eq!(
+            "00:1B:00:28:F2:00",
+            cnv.convert(&[0x00, 0x1B, 0x00, 0x28, 0xF2, 0x00])
+        );
+        assert_eq!(
+            "00:1B:00:28:F2:00",
+            cnv.convert(&[0x00, 0x1B, 0x00, 0x28, 0xF2, 0x00, 0x00, 0x00])
+        );
+    }
 }
@@ -3,15 +3,15 @@ use std::io::Read;
 use crate::{read_u16, read_u32, read_u8};
 
 #[derive(Debug)]
-#[allow(unused)]
 /// A struct that contains the definitions of all the columns in the file.
 /// The byte layout of this section is described [here](https://www.vertica.com/docs/9.3.x/HTML/Content/Authoring/AdministratorsGuide/BinaryFilesAppendix/ColumnDefinitions.htm).
 ///
 pub struct ColumnDefinitions {
-    header_length: u32,
-    version: u16,
+    /// The length of the rest of the header, after this field
+    pub header_length: u32,
+    pub version: u16,
     // filler
-    number_of_columns: u16,
+    pub number_of_columns: u16,
     pub column_widths: Vec<u32>,
 }
 
@@ -45,6 +45,12 @@ impl ColumnDefinitions {
             column_widths,
         })
     }
+
+    /// The number of bytes the definitions take up in the file, including the header length.
+    /// This is worked out from the number of columns, rather than trusting `header_length`.
+    pub fn length(&self) -> usize {
+        4 + 2 + 1 + 2 + 4 * self.column_widths.len()
+    }
 }
 
 #[cfg(test)]
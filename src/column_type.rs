@@ -1,18 +1,33 @@
 use std::convert::TryInto;
+use std::fmt::{self, Write};
 use std::ops::Add;
-use std::panic;
 
 use anyhow::bail;
 use chrono::prelude::*;
 use chrono::Duration;
 use lazy_static::lazy_static;
-use regex;
 use regex::Regex;
 use uuid::Uuid;
 
+use crate::args::{FloatFormat, IntervalFormat};
+use crate::binary_encoding::BinaryEncoding;
 use crate::column_conversion::ColumnConversion;
+use crate::column_value::ColumnValue;
+use crate::time_format::{Temporal, TimeFormat};
 use crate::Args;
 
+/// Vertica stores a `date` or `timestamp` of `infinity` as the largest `i64`, and `-infinity` as
+/// the smallest but one. The smallest is taken to be `-infinity`, too.
+pub(crate) const INFINITY: i64 = i64::MAX;
+pub(crate) const NEGATIVE_INFINITY: i64 = i64::MIN + 1;
+
+/// The furthest a date can be from 2000-01-01, in days, and still be decoded into a chrono date.
+/// It's about 260,000 years; chrono goes to about 262,000, which leaves room for moving
+/// timestamps into other time zones.
+const MAX_DAYS: i64 = 95_000_000;
+
+pub(crate) const MICROSECONDS_PER_DAY: i64 = 86_400_000_000;
+
 #[derive(Debug, PartialEq)]
 /// An enum of all possible Vertica data types. We read in a file of type information to
 /// create this data structure. The format of the file looks like this
@@ -22,7 +37,7 @@ use crate::Args;
 /// where
 /// * `type` is the actual data type, lowercase, that matches the values in the enum,
 /// * `name` is an optional name of the column for use in the file. The name can be different from what
-///    the column was named in the table
+///   the column was named in the table
 /// * `conversion` is an optional value to request conversion of the value. The possible values
 ///   are `ipaddress` and `macaddress`
 ///
@@ -63,8 +78,11 @@ pub enum ColumnType {
     Varbinary,
     Binary,
     Numeric,
+    /// An `interval day to second`, or any other interval of days, hours, minutes, or seconds
     Interval,
-    UUID,
+    /// An `interval year to month`, `interval year`, or `interval month`
+    IntervalYearToMonth,
+    Uuid,
 }
 
 impl ColumnType {
@@ -75,8 +93,24 @@ impl ColumnType {
         }
 
         let no_parens = PAREN_REGEX.replace(string, "");
+        let lowercase = no_parens.to_lowercase();
+        let words: Vec<&str> = lowercase.split_whitespace().collect();
+
+        // Intervals can have a qualifier, like `interval day to second`, which says which
+        // fields they hold, and so how they're stored.
+        if words.len() > 1 && words[0] == "interval" {
+            return match &words[1..] {
+                ["year"] | ["month"] | ["year", "to", "month"] => {
+                    Ok(ColumnType::IntervalYearToMonth)
+                }
+                [from] | [from, "to", _] if ["day", "hour", "minute", "second"].contains(from) => {
+                    Ok(ColumnType::Interval)
+                }
+                _ => bail!("invalid type: {}", string),
+            };
+        }
 
-        let result = match no_parens.to_lowercase().as_str() {
+        let result = match lowercase.as_str() {
             "integer" | "int" => ColumnType::Integer,
             "float" => ColumnType::Float,
             "char" => ColumnType::Char,
@@ -91,203 +125,569 @@ impl ColumnType {
             "binary" => ColumnType::Binary,
             "numeric" => ColumnType::Numeric,
             "interval" => ColumnType::Interval,
-            "uuid" => ColumnType::UUID,
+            "uuid" => ColumnType::Uuid,
             _ => bail!("invalid type: {}", string),
         };
 
         Ok(result)
     }
 
-    /// Format the passed-in vector of `u8`s, into Strings, suitable for use in CSV files.
+    /// Write a date or timestamp that's too far from 2000-01-01 for chrono, with its date worked
+    /// out by `civil_from_days`. A `timestamptz` is always written in UTC, since there's no
+    /// telling what the offset of a zone was, or will be, that far away.
     ///
-    /// * `value` - the vector of bytes to format
-    /// * `tz_offset` - number of hours to adjust times
-    /// * `column_conversion` - an optional converter for certain data types
+    /// * `out` - where to write the value
+    /// * `value` - the days, for a date, or microseconds, for a timestamp, since 2000-01-01
+    /// * `precision` - how many digits of fractional seconds to write
+    fn write_out_of_range(
+        &self,
+        out: &mut impl Write,
+        value: i64,
+        precision: Option<u32>,
+    ) -> fmt::Result {
+        if *self == ColumnType::Date {
+            let (year, month, day) = civil_from_days(value);
+            write_date(out, year, month, day)?;
+            return write_era(out, year);
+        }
+
+        let (year, month, day) = civil_from_days(value.div_euclid(MICROSECONDS_PER_DAY));
+        let micros = value.rem_euclid(MICROSECONDS_PER_DAY);
+        let time = NaiveTime::from_num_seconds_from_midnight(
+            (micros / 1_000_000) as u32,
+            (micros % 1_000_000) as u32 * 1000,
+        );
+
+        write_date(out, year, month, day)?;
+        write!(out, " {}", time.format("%T"))?;
+        write_fraction(out, time.nanosecond(), precision)?;
+
+        if *self == ColumnType::TimestampTz {
+            write_offset(out, 0)?;
+        }
+
+        write_era(out, year)
+    }
+
+    /// The bytes of a `binary` or `varbinary` value to write as they are, when the
+    /// `--binary-encoding` is `raw`, or `None` if the value is written as text, like every value
+    /// of other types, and addresses.
     ///
-    /// Note that all multi-byte values in the native file are stored in little-endian
-    /// format, so we need to be mindful of that.
+    /// * `value` - the value's bytes
+    /// * `column_conversion` - the column's conversion, if it has one
+    /// * `args` - all the command line arguments
+    pub fn raw_bytes<'b>(
+        &self,
+        value: &'b [u8],
+        column_conversion: &Option<ColumnConversion>,
+        args: &Args,
+    ) -> Option<&'b [u8]> {
+        let is_address = matches!(
+            column_conversion,
+            Some(ColumnConversion::IpAddress | ColumnConversion::MacAddress)
+        );
+
+        match self {
+            ColumnType::Binary | ColumnType::Varbinary
+                if args.binary_encoding == BinaryEncoding::Raw && !is_address =>
+            {
+                Some(self.binary_bytes(value, args))
+            }
+            _ => None,
+        }
+    }
+
+    /// A `binary` or `varbinary` value's bytes, without the zeros padding a `binary` value, with
+    /// `--trim-binary`.
+    fn binary_bytes<'b>(&self, bytes: &'b [u8], args: &Args) -> &'b [u8] {
+        if *self == ColumnType::Binary && args.trim_binary {
+            let end = bytes.iter().rposition(|b| *b != 0x00).map_or(0, |i| i + 1);
+            &bytes[..end]
+        } else {
+            bytes
+        }
+    }
+
+    /// Whether the type is a date or a time, which can have a format in the types file.
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::Date
+                | ColumnType::Time
+                | ColumnType::TimeTz
+                | ColumnType::Timestamp
+                | ColumnType::TimestampTz
+        )
+    }
+
+    /// Whether the number in parentheses after the type, if there is one, is a precision, like
+    /// in `timestamp(3)`, rather than a length.
+    pub fn has_precision(&self) -> bool {
+        matches!(
+            self,
+            ColumnType::Time
+                | ColumnType::TimeTz
+                | ColumnType::Timestamp
+                | ColumnType::TimestampTz
+                | ColumnType::Interval
+        )
+    }
+
+    /// Format the passed-in slice of `u8`s into a String, suitable for use in CSV files.
+    ///
+    /// * `value` - the bytes to format, or `None` for a null
+    /// * `column_conversion` - an optional converter for certain data types
+    /// * `precision` - how many digits of fractional seconds to write, from the type's precision,
+    ///   like the 3 in `timestamp(3)`; `None` writes 6
     pub fn format_value(
         &self,
-        value: &Option<Vec<u8>>,
-        tz_offset: i8,
+        value: Option<&[u8]>,
         column_conversion: &Option<ColumnConversion>,
+        precision: Option<u32>,
         args: &Args,
     ) -> String {
-        match &value {
-            Some(value) => {
-                match &*self {
-                    ColumnType::Integer => {
-                        let bytes = &value[..];
-
-                        match bytes.len() {
-                            8 => format!("{}", i64::from_le_bytes(bytes.try_into().unwrap())),
-                            4 => format!("{}", i32::from_le_bytes(bytes.try_into().unwrap())),
-                            2 => format!("{}", i16::from_le_bytes(bytes.try_into().unwrap())),
-                            1 => format!("{}", i8::from_le_bytes(bytes.try_into().unwrap())),
-                            _ => panic!("incorrect integer byte count"),
-                        }
-                    }
-                    ColumnType::Float => {
-                        let bytes = &value[..];
-                        format!("{}", f64::from_le_bytes(bytes.try_into().unwrap()))
-                    }
-                    ColumnType::Char | ColumnType::Varchar => {
-                        let char_str = match std::str::from_utf8(&value) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                eprintln!("couldn't convert {:X?} to a string: {}", &value, e);
-                                "INVALID"
-                            }
-                        };
-
-                        format!("{}", char_str.trim())
+        let mut output = String::new();
+
+        self.write_value(&mut output, value, column_conversion, precision, args)
+            .expect("formatting into a String can't fail");
+
+        output
+    }
+
+    /// Decode the passed-in slice of `u8`s into a `ColumnValue`, according to the type of `self`.
+    ///
+    /// * `value` - the bytes to decode, or `None` for a null
+    ///
+    /// Note that all multi-byte values in the native file are stored in little-endian
+    /// format, so we need to be mindful of that.
+    pub fn decode<'a>(&self, value: Option<&'a [u8]>) -> ColumnValue<'a> {
+        let bytes = match value {
+            Some(bytes) => bytes,
+            None => return ColumnValue::Null,
+        };
+
+        match self {
+            ColumnType::Integer => ColumnValue::Integer(match bytes.len() {
+                8 => i64::from_le_bytes(bytes.try_into().unwrap()),
+                4 => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                2 => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                1 => i8::from_le_bytes(bytes.try_into().unwrap()) as i64,
+                _ => panic!("incorrect integer byte count"),
+            }),
+            ColumnType::Float => ColumnValue::Float(f64::from_le_bytes(bytes.try_into().unwrap())),
+            ColumnType::Char | ColumnType::Varchar => {
+                let char_str = match std::str::from_utf8(bytes) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("couldn't convert {:X?} to a string: {}", bytes, e);
+                        "INVALID"
                     }
-                    ColumnType::Boolean => format!("{}", value[0]),
-                    ColumnType::Date => {
-                        let bytes = &value[..];
-                        let julian_date_offset =
-                            u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
+                };
+
+                ColumnValue::String(char_str.trim())
+            }
+            ColumnType::Boolean => ColumnValue::Boolean(bytes[0] != 0),
+            ColumnType::Date => {
+                let julian_date_offset = u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
+
+                match julian_date_offset {
+                    INFINITY => ColumnValue::Infinity,
+                    NEGATIVE_INFINITY | i64::MIN => ColumnValue::NegativeInfinity,
+                    days if days.abs() > MAX_DAYS => ColumnValue::OutOfRange(days),
+                    days => {
                         let vertica_epoch_date = NaiveDate::from_ymd(2000, 1, 1);
-                        let d = Duration::days(julian_date_offset);
-                        let new_date = vertica_epoch_date.add(d);
-                        format!("{}", new_date)
-                    }
-                    ColumnType::Timestamp => {
-                        let bytes = &value[..];
-                        let julian_date_offset =
-                            u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
-                        let vertica_epoch_date = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
-
-                        let d = Duration::microseconds(julian_date_offset);
-                        let new_date = vertica_epoch_date.add(d);
-                        format!("{}", new_date)
+                        ColumnValue::Date(vertica_epoch_date.add(Duration::days(days)))
                     }
-                    ColumnType::TimestampTz => {
-                        let bytes = &value[..];
-                        let julian_date_offset =
-                            u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
-                        let vertica_epoch_date =
-                            NaiveDate::from_ymd(2000, 1, 1).and_hms_micro(0, 0, 0, 0);
-
-                        let d = Duration::microseconds(julian_date_offset);
-                        let new_date = vertica_epoch_date.add(d);
-
-                        let tz_offset_date = if tz_offset != 0 {
-                            let tz_offset_hours = Duration::hours(tz_offset as i64);
-                            new_date.add(tz_offset_hours)
-                        } else {
-                            new_date
-                        };
-
-                        let formatted_tz_offset = format!("{:+03}", tz_offset);
-                        let formatted_date =
-                            format!("{}{}", tz_offset_date.format("%F %T"), formatted_tz_offset);
-                        format!("{}", formatted_date)
+                }
+            }
+            ColumnType::Timestamp | ColumnType::TimestampTz => {
+                let julian_date_offset = u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
+
+                match julian_date_offset {
+                    INFINITY => return ColumnValue::Infinity,
+                    NEGATIVE_INFINITY | i64::MIN => return ColumnValue::NegativeInfinity,
+                    micros if micros.div_euclid(MICROSECONDS_PER_DAY).abs() > MAX_DAYS => {
+                        return ColumnValue::OutOfRange(micros)
                     }
-                    ColumnType::Time => {
-                        let bytes = &value[..];
-                        let microsecond_offset =
-                            u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
+                    _ => {}
+                }
 
-                        let midnight = NaiveTime::from_hms_micro(0, 0, 0, 0);
+                let vertica_epoch_date = NaiveDate::from_ymd(2000, 1, 1).and_hms_micro(0, 0, 0, 0);
 
-                        let d = Duration::microseconds(microsecond_offset);
-                        let new_time = midnight.add(d);
+                let d = Duration::microseconds(julian_date_offset);
+                let new_date = vertica_epoch_date.add(d);
 
-                        format!("{}", new_time)
-                    }
-                    ColumnType::TimeTz => {
-                        let bytes = &value[..];
-                        let as_u64 = u64::from_le_bytes(bytes.try_into().unwrap());
+                if *self == ColumnType::Timestamp {
+                    ColumnValue::Timestamp(new_date)
+                } else {
+                    ColumnValue::TimestampTz(new_date)
+                }
+            }
+            ColumnType::Time => {
+                let microsecond_offset = u64::from_le_bytes(bytes.try_into().unwrap()) as i64;
 
-                        let microsecond_offset: u64 = as_u64 >> 24;
-                        let tz_offset_from_column: i64 = (as_u64 & 0xFFFFFF) as i64;
+                let midnight = NaiveTime::from_hms_micro(0, 0, 0, 0);
 
-                        let new_offset = -((tz_offset_from_column / 3600) - 24);
-                        let midnight = NaiveTime::from_hms_micro(0, 0, 0, 0);
+                let d = Duration::microseconds(microsecond_offset);
+                ColumnValue::Time(midnight.add(d))
+            }
+            ColumnType::TimeTz => {
+                let as_u64 = u64::from_le_bytes(bytes.try_into().unwrap());
 
-                        let d = Duration::microseconds(microsecond_offset as i64);
-                        let new_time = midnight.add(d);
+                // The top 40 bits are the time in UTC, in microseconds since midnight. The
+                // bottom 24 are the zone's offset in seconds west of UTC, plus 24 hours, so
+                // they're never negative. Keeping the offset in seconds, rather than hours,
+                // handles zones that aren't a whole number of hours from UTC.
+                let microsecond_offset: u64 = as_u64 >> 24;
+                let tz_offset_from_column: i32 = (as_u64 & 0xFFFFFF) as i32;
 
-                        // if we leave this as minutes, then we can handle timezones that
-                        // don't align to an hour boundary
-                        let tz_offset_hours = Duration::hours(new_offset as i64);
-                        let offset_time = new_time.add(tz_offset_hours);
+                let offset_seconds = 24 * 3600 - tz_offset_from_column;
+                let midnight = NaiveTime::from_hms_micro(0, 0, 0, 0);
 
-                        let formatted_tz_offset = format!("{:+03}", new_offset);
-                        let formatted_date =
-                            format!("{}{}", offset_time.format("%T"), formatted_tz_offset);
-                        format!("{}", formatted_date)
-                    }
-                    ColumnType::Varbinary | ColumnType::Binary => {
-                        let bytes = &value[..];
-                        let filtered_bytes = bytes[..]
-                            .iter()
-                            .filter(|&b| *b != 0x00u8)
-                            .map(|b| *b)
-                            .collect::<Vec<u8>>();
-
-                        match column_conversion {
-                            None => {
-                                let byte_values: String =
-                                    filtered_bytes.iter().map(|b| format!("{:X?}", b)).collect();
-
-                                let prefix = if args.hex_prefix { "0x" } else { "" };
-
-                                format!("{}{}", prefix, byte_values)
-                            }
-                            Some(conversion) => conversion.convert(filtered_bytes),
-                        }
-                    }
-                    ColumnType::Numeric => {
-                        let bytes = &value[..];
-                        let mut chunks: Vec<u64> = vec![];
+                let d = Duration::microseconds(microsecond_offset as i64);
+
+                ColumnValue::TimeTz(midnight.add(d), offset_seconds)
+            }
+            ColumnType::Varbinary | ColumnType::Binary => ColumnValue::Binary(bytes),
+            ColumnType::Numeric => ColumnValue::Numeric(bytes),
+            ColumnType::Interval => {
+                ColumnValue::Interval(i64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            ColumnType::IntervalYearToMonth => {
+                ColumnValue::IntervalYearToMonth(i64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            ColumnType::Uuid => {
+                let mut uuid_bytes = [0; 16];
+                uuid_bytes[..15].copy_from_slice(&bytes[..15]);
 
-                        for i in 0..(bytes.len() / 8) {
-                            let chunk = u64::from_le_bytes(
-                                bytes[(i * 8)..((i + 1) * 8)].try_into().unwrap(),
-                            );
+                ColumnValue::Uuid(Uuid::from_bytes(uuid_bytes))
+            }
+        }
+    }
 
-                            chunks.push(chunk);
-                        }
+    /// Format the passed-in slice of `u8`s, writing it straight into `out`. This is the same
+    /// as `format_value`, but lets the caller reuse a buffer rather than allocating a new
+    /// String for every value.
+    ///
+    /// * `out` - where to write the formatted value
+    /// * `value` - the bytes to format, or `None` for a null
+    /// * `column_conversion` - an optional converter for certain data types
+    /// * `precision` - how many digits of fractional seconds to write, from the type's precision,
+    ///   like the 3 in `timestamp(3)`; `None` writes 6
+    pub fn write_value(
+        &self,
+        out: &mut impl Write,
+        value: Option<&[u8]>,
+        column_conversion: &Option<ColumnConversion>,
+        precision: Option<u32>,
+        args: &Args,
+    ) -> fmt::Result {
+        let precision = args.fractional_digits.or(precision);
+        let value = self.decode(value);
 
-                        let filtered_chunks: Vec<String> = chunks
-                            .iter()
-                            .skip_while(|chunk| **chunk == 0)
-                            .map(|chunk| chunk.to_string())
-                            .collect();
+        if let Some(format) = TimeFormat::for_column(column_conversion, args) {
+            if let Some(temporal) = Temporal::from_value(&value, args) {
+                return format.write(out, temporal, precision);
+            }
+        }
 
-                        filtered_chunks.join("")
-                    }
-                    ColumnType::Interval => {
-                        let bytes = &value[..];
-                        let interval_microseconds = i64::from_le_bytes(bytes.try_into().unwrap());
+        match value {
+            ColumnValue::Null => Ok(()),
+            ColumnValue::Integer(value) => write!(out, "{}", value),
+            ColumnValue::Float(value) => write_float(out, value, args),
+            ColumnValue::String(value) => out.write_str(value),
+            ColumnValue::Boolean(value) => {
+                let format = match column_conversion {
+                    Some(ColumnConversion::BoolFormat(format)) => format,
+                    _ => &args.bool_format,
+                };
+
+                out.write_str(format.text(value))
+            }
+            ColumnValue::Date(value) => {
+                write_date(out, value.year() as i64, value.month(), value.day())?;
+                write_era(out, value.year() as i64)
+            }
+            ColumnValue::Timestamp(value) => {
+                write_date(out, value.year() as i64, value.month(), value.day())?;
+                write!(out, " {}", value.format("%T"))?;
+                write_fraction(out, value.nanosecond(), precision)?;
+                write_era(out, value.year() as i64)
+            }
+            ColumnValue::TimestampTz(value) => {
+                let (local, offset_seconds) = args.time_zone().local(value);
+
+                write_date(out, local.year() as i64, local.month(), local.day())?;
+                write!(out, " {}", local.format("%T"))?;
+                write_fraction(out, local.nanosecond(), precision)?;
+                write_offset(out, offset_seconds)?;
+                write_era(out, local.year() as i64)
+            }
+            ColumnValue::Infinity => out.write_str("infinity"),
+            ColumnValue::NegativeInfinity => out.write_str("-infinity"),
+            ColumnValue::OutOfRange(value) => self.write_out_of_range(out, value, precision),
+            ColumnValue::Time(value) => {
+                write!(out, "{}", value.format("%T"))?;
+                write_fraction(out, value.nanosecond(), precision)
+            }
+            ColumnValue::TimeTz(value, offset_seconds) => {
+                let offset_time = value.add(Duration::seconds(offset_seconds as i64));
 
-                        let seconds = interval_microseconds / 1_000_000;
-                        let (hours, remainder) = ((seconds / 3600), (seconds % 3600));
-                        let (minutes, remainder) = ((remainder / 60), (remainder % 60));
+                write!(out, "{}", offset_time.format("%T"))?;
+                write_fraction(out, offset_time.nanosecond(), precision)?;
+                write_offset(out, offset_seconds)
+            }
+            ColumnValue::Binary(bytes) => match column_conversion {
+                Some(conversion @ (ColumnConversion::IpAddress | ColumnConversion::MacAddress)) => {
+                    out.write_str(&conversion.convert(bytes))
+                }
+                _ => {
+                    args.binary_encoding
+                        .write(out, self.binary_bytes(bytes, args), args.hex_prefix)
+                }
+            },
+            ColumnValue::Numeric(bytes) => {
+                let chunks = bytes
+                    .chunks_exact(8)
+                    .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                    .skip_while(|chunk| *chunk == 0);
+
+                for chunk in chunks {
+                    write!(out, "{}", chunk)?;
+                }
 
-                        format!("{:02}:{:02}:{:02}", hours, minutes, remainder)
-                    }
-                    ColumnType::UUID => {
-                        let tmp_bytes = &value[..];
-                        let mut bytes = [0; 16];
+                Ok(())
+            }
+            ColumnValue::Interval(interval_microseconds) => {
+                write_interval(out, interval_microseconds, args.interval_format, precision)
+            }
+            ColumnValue::IntervalYearToMonth(months) => {
+                let sign = if months < 0 { "-" } else { "" };
+                let (years, months) = (months.unsigned_abs() / 12, months.unsigned_abs() % 12);
+
+                match args.interval_format {
+                    IntervalFormat::Vertica => write!(out, "{}{}-{:02}", sign, years, months),
+                    IntervalFormat::Iso8601 if years == 0 => write!(out, "{}P{}M", sign, months),
+                    IntervalFormat::Iso8601 if months == 0 => write!(out, "{}P{}Y", sign, years),
+                    IntervalFormat::Iso8601 => write!(out, "{}P{}Y{}M", sign, years, months),
+                }
+            }
+            ColumnValue::Uuid(uuid) => write!(out, "{}", uuid),
+        }
+    }
+}
 
-                        for i in 0..15 {
-                            bytes[i] = tmp_bytes[i];
-                        }
+/// Write an interval of days, hours, minutes, and seconds. In Vertica's form, that's
+/// `D HH:MM:SS.ffffff`, with the days left off if there aren't any, and as many digits of the
+/// fraction as the precision says, or 6 without one. In ISO 8601's, it's `PnDTnHnMn.nS`, with any
+/// zero fields left off.
+///
+/// * `out` - where to write the interval
+/// * `microseconds` - the length of the interval
+/// * `format` - which form to write it in
+/// * `precision` - how many digits of fractional seconds to write in Vertica's form; `None`
+///   writes 6
+fn write_interval(
+    out: &mut impl Write,
+    microseconds: i64,
+    format: IntervalFormat,
+    precision: Option<u32>,
+) -> fmt::Result {
+    if microseconds < 0 {
+        out.write_char('-')?;
+    }
 
-                        let uuid = Uuid::from_bytes(bytes);
+    let microseconds = microseconds.unsigned_abs();
+    let (seconds, fraction) = (microseconds / 1_000_000, microseconds % 1_000_000);
+    let (days, hours) = (seconds / 86400, seconds % 86400 / 3600);
+    let (minutes, seconds) = (seconds % 3600 / 60, seconds % 60);
 
-                        format!("{}", uuid)
-                    }
-                }
+    match format {
+        IntervalFormat::Vertica => {
+            if days != 0 {
+                write!(out, "{} ", days)?;
+            }
+
+            write!(out, "{:02}:{:02}:{:02}", hours, minutes, seconds)?;
+
+            write_fraction(out, fraction as u32 * 1000, precision)
+        }
+        IntervalFormat::Iso8601 => {
+            out.write_char('P')?;
+
+            if days != 0 {
+                write!(out, "{}D", days)?;
+            }
+
+            if hours == 0 && minutes == 0 && seconds == 0 && fraction == 0 {
+                return if days == 0 {
+                    out.write_str("T0S")
+                } else {
+                    Ok(())
+                };
+            }
+
+            out.write_char('T')?;
+
+            if hours != 0 {
+                write!(out, "{}H", hours)?;
+            }
+
+            if minutes != 0 {
+                write!(out, "{}M", minutes)?;
+            }
+
+            if fraction != 0 {
+                let fraction = format!("{:06}", fraction);
+                write!(out, "{}.{}S", seconds, fraction.trim_end_matches('0'))
+            } else if seconds != 0 {
+                write!(out, "{}S", seconds)
+            } else {
+                Ok(())
             }
-            _ => "".to_string(),
         }
     }
 }
 
+/// Write a float in the format from `--float-format`. NaN and the infinities are written as the
+/// text from `--nan-as` and `--infinity-as`, since none of the formats has a way to write them.
+///
+/// * `out` - where to write the float
+/// * `value` - the float
+/// * `args` - the command line arguments
+fn write_float(out: &mut impl Write, value: f64, args: &Args) -> fmt::Result {
+    if value.is_nan() {
+        return out.write_str(&args.nan_as);
+    }
+
+    if value.is_infinite() {
+        if value < 0.0 {
+            out.write_char('-')?;
+        }
+
+        return out.write_str(&args.infinity_as);
+    }
+
+    match args.float_format {
+        FloatFormat::Shortest => write!(out, "{}", value),
+        FloatFormat::Fixed(digits) => write!(out, "{:.*}", digits, value),
+        FloatFormat::Scientific => {
+            // Rust writes `1.5e6`, with the shortest mantissa, but no sign or padding on the
+            // exponent.
+            let scientific = format!("{:e}", value);
+            let (mantissa, exponent) = scientific.split_once('e').unwrap();
+            let exponent: i32 = exponent.parse().unwrap();
+
+            write!(out, "{}e{:+03}", mantissa, exponent)
+        }
+    }
+}
+
+/// Write a date as `YYYY-MM-DD`, the way Vertica does. A year before 1 AD is written as a year
+/// BC, and `write_era` adds the ` BC` at the end of the value. There's no year 0, so chrono's
+/// year 0 is 1 BC, and its year -1 is 2 BC.
+///
+/// * `out` - where to write the date
+/// * `year` - the year, with 1 BC as 0
+/// * `month` - the month, from 1
+/// * `day` - the day of the month, from 1
+fn write_date(out: &mut impl Write, year: i64, month: u32, day: u32) -> fmt::Result {
+    let year = if year <= 0 { 1 - year } else { year };
+
+    write!(out, "{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Write ` BC` after a value from before 1 AD.
+///
+/// * `out` - where to write the era
+/// * `year` - the year, with 1 BC as 0
+fn write_era(out: &mut impl Write, year: i64) -> fmt::Result {
+    if year <= 0 {
+        out.write_str(" BC")?;
+    }
+
+    Ok(())
+}
+
+/// The year, month, and day of a number of days since 2000-01-01, in the proleptic Gregorian
+/// calendar, for dates too far away for chrono. It's Howard Hinnant's `civil_from_days`, done
+/// in `i128`, so that no `i64` can overflow it.
+///
+/// * `days` - the number of days since 2000-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Count from 0000-03-01, so that leap days are at the end of the year.
+    let days = days as i128 + 730_425;
+
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year as i64, month as u32, day as u32)
+}
+
+/// How many digits of fractional seconds are written when a value's type has no precision, and
+/// there's no `--fractional-digits`: all 6 of its microseconds, like Vertica's default.
+const DEFAULT_PRECISION: u32 = 6;
+
+/// Write the fractional part of a second, with its decimal point. It's always exactly
+/// `precision` digits, even if they're zeros, or `DEFAULT_PRECISION` without one, so every value
+/// in a column has the same number of digits.
+///
+/// * `out` - where to write the fraction
+/// * `nanoseconds` - the fraction, in nanoseconds
+/// * `precision` - how many digits to write, from 0 to 9
+pub(crate) fn write_fraction(
+    out: &mut impl Write,
+    nanoseconds: u32,
+    precision: Option<u32>,
+) -> fmt::Result {
+    // A leap second is stored as more than a billion nanoseconds.
+    let nanoseconds = nanoseconds % 1_000_000_000;
+
+    let digits = precision.unwrap_or(DEFAULT_PRECISION).min(9) as usize;
+
+    if digits == 0 {
+        return Ok(());
+    }
+
+    write!(out, ".{}", &format!("{:09}", nanoseconds)[..digits])
+}
+
+/// Write an offset from UTC in RFC 3339 form, like `+05:30`, or `-08:00`. Seconds are only
+/// written if there are any, as `+00:19:32`, since a few historical zones have them.
+///
+/// * `out` - where to write the offset
+/// * `offset_seconds` - the offset, in seconds east of UTC
+pub(crate) fn write_offset(out: &mut impl Write, offset_seconds: i32) -> fmt::Result {
+    let sign = if offset_seconds < 0 { '-' } else { '+' };
+    let offset = offset_seconds.abs();
+
+    write!(
+        out,
+        "{}{:02}:{:02}",
+        sign,
+        offset / 3600,
+        offset % 3600 / 60
+    )?;
+
+    if offset % 60 != 0 {
+        write!(out, ":{:02}", offset % 60)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     mod column_type_tests {
@@ -315,12 +715,43 @@ mod tests {
 
             assert!(val.is_err(), "should not have returned valid enum");
         }
+
+        #[test]
+        fn test_interval_qualifiers() {
+            let cases = vec![
+                ("Interval", ColumnType::Interval),
+                ("Interval Day to Second", ColumnType::Interval),
+                ("interval  hour to minute", ColumnType::Interval),
+                ("Interval Day to Second(3)", ColumnType::Interval),
+                ("Interval Second", ColumnType::Interval),
+                ("Interval Year to Month", ColumnType::IntervalYearToMonth),
+                ("INTERVAL YEAR", ColumnType::IntervalYearToMonth),
+                ("Interval Month", ColumnType::IntervalYearToMonth),
+            ];
+
+            for (input, expected) in cases {
+                assert_eq!(
+                    ColumnType::from_string(input).unwrap(),
+                    expected,
+                    "{}",
+                    input
+                );
+            }
+
+            assert!(ColumnType::from_string("Interval Week").is_err());
+            assert!(ColumnType::from_string("Interval Year to Second").is_err());
+        }
     }
 
     mod format_tests {
-        use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-
-        use crate::column_type::ColumnType;
+        use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+        use crate::args::{BoolFormat, FloatFormat, IntervalFormat};
+        use crate::assemble::{encode_date, encode_time_tz, encode_timestamp};
+        use crate::binary_encoding::BinaryEncoding;
+        use crate::column_conversion::ColumnConversion;
+        use crate::column_type::{civil_from_days, ColumnType};
+        use crate::column_value::ColumnValue;
         use crate::Args;
 
         #[test]
@@ -339,7 +770,8 @@ mod tests {
             for (input, expected_output) in inputs.iter().zip(expected_outputs) {
                 let byte_vec_option: Option<Vec<u8>> = Some(vec![*input]);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -365,7 +797,8 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -413,7 +846,8 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -482,7 +916,8 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -502,17 +937,51 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
         }
 
+        #[test]
+        fn test_float_formats() {
+            let mut args = Args::with_defaults();
+
+            let format = |value: f64, args: &Args| {
+                ColumnType::Float.format_value(Some(&value.to_le_bytes()), &None, None, args)
+            };
+
+            assert_eq!(format(f64::NAN, &args), "NaN");
+            assert_eq!(format(f64::INFINITY, &args), "Infinity");
+            assert_eq!(format(f64::NEG_INFINITY, &args), "-Infinity");
+            assert_eq!(format(1.5e6, &args), "1500000");
+            assert_eq!(format(0.1 + 0.2, &args), "0.30000000000000004");
+
+            args.float_format = FloatFormat::Fixed(3);
+            assert_eq!(format(2.0 / 3.0, &args), "0.667");
+            assert_eq!(format(-1.5, &args), "-1.500");
+
+            args.float_format = FloatFormat::Fixed(0);
+            assert_eq!(format(2.5, &args), "2");
+
+            args.float_format = FloatFormat::Scientific;
+            assert_eq!(format(1.5e6, &args), "1.5e+06");
+            assert_eq!(format(-0.000123, &args), "-1.23e-04");
+            assert_eq!(format(1e300, &args), "1e+300");
+            assert_eq!(format(0.0, &args), "0e+00");
+
+            args.nan_as = "".to_string();
+            args.infinity_as = "inf".to_string();
+            assert_eq!(format(f64::NAN, &args), "");
+            assert_eq!(format(f64::NEG_INFINITY, &args), "-inf");
+        }
+
         #[test]
         fn test_char() {
             let column_type = ColumnType::Char;
 
-            let inputs: Vec<u8> = vec!['a' as u8, 'A' as u8, 'z' as u8, 'Z' as u8];
+            let inputs: Vec<u8> = vec![b'a', b'A', b'z', b'Z'];
 
             let args = Args::with_defaults();
 
@@ -521,7 +990,8 @@ mod tests {
             for (input, expected_output) in inputs.iter().zip(expected_outputs) {
                 let byte_vec_option: Option<Vec<u8>> = Some(vec![*input]);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -541,7 +1011,8 @@ mod tests {
                 let bytes = input.as_bytes();
                 let byte_vec_option: Option<Vec<u8>> = Some(bytes.to_vec());
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -551,19 +1022,33 @@ mod tests {
         fn test_boolean() {
             let column_type = ColumnType::Boolean;
 
-            let inputs: Vec<u8> = vec![1, 0];
+            let inputs: Vec<u8> = vec![1, 0, 2, 0xFF];
 
-            let args = Args::with_defaults();
+            let mut args = Args::with_defaults();
 
-            let expected_outputs = vec!["1", "0"];
+            let expected_outputs = vec!["1", "0", "1", "1"];
 
             for (input, expected_output) in inputs.iter().zip(expected_outputs) {
                 let byte_vec_option: Option<Vec<u8>> = Some(vec![*input]);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
+
+            args.bool_format = BoolFormat::Words;
+            assert_eq!(
+                column_type.format_value(Some(&[2]), &None, None, &args),
+                "true"
+            );
+
+            // The column's own format is used before the one from the command line.
+            let column_format = Some(ColumnConversion::BoolFormat(BoolFormat::Letters));
+            assert_eq!(
+                column_type.format_value(Some(&[0]), &column_format, None, &args),
+                "f"
+            );
         }
 
         #[test]
@@ -576,7 +1061,7 @@ mod tests {
             let inputs: Vec<i64> = expected_outputs
                 .iter()
                 .map(|date_str| {
-                    let date = NaiveDate::parse_from_str(*date_str, "%Y-%m-%d").unwrap();
+                    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").unwrap();
                     let days_between = date - vertica_epoch_date;
                     days_between.num_days()
                 })
@@ -590,7 +1075,8 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(expected_output, output);
             }
@@ -612,7 +1098,7 @@ mod tests {
             let inputs: Vec<i64> = expected_outputs
                 .iter()
                 .map(|date_str| {
-                    let date = match NaiveDateTime::parse_from_str(*date_str, "%Y-%m-%d %H:%M:%S") {
+                    let date = match NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S") {
                         Ok(d) => d,
                         Err(e) => panic!("{}", e),
                     };
@@ -633,9 +1119,10 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
-                assert_eq!(expected_output, output);
+                assert_eq!(format!("{}.000000", expected_output), output);
             }
         }
 
@@ -655,13 +1142,13 @@ mod tests {
 
             let expected_outputs: Vec<String> = string_inputs
                 .iter()
-                .map(|s| s[0..(s.len() - 2)].to_string())
+                .map(|s| format!("{}.000000{}:00", &s[0..19], &s[19..(s.len() - 2)]))
                 .collect();
 
             let inputs: Vec<i64> = string_inputs
                 .iter()
                 .map(|date_str| {
-                    let date = match NaiveDateTime::parse_from_str(*date_str, "%Y-%m-%d %H:%M:%S%z")
+                    let date = match NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S%z")
                     {
                         Ok(d) => d,
                         Err(e) => panic!("{}", e),
@@ -683,12 +1170,213 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(output, expected_output);
             }
         }
 
+        #[test]
+        fn test_timestamptz_in_time_zone() {
+            let column_type = ColumnType::TimestampTz;
+            let vertica_epoch_date = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
+
+            let inputs = [
+                NaiveDate::from_ymd(1999, 1, 8).and_hms(12, 4, 37),
+                NaiveDate::from_ymd(2021, 7, 1).and_hms(12, 0, 0),
+            ];
+
+            let mut args = Args::with_defaults();
+
+            let format = |input: &NaiveDateTime, args: &Args| {
+                let micros = (*input - vertica_epoch_date).num_microseconds().unwrap();
+                column_type.format_value(Some(&micros.to_le_bytes()), &None, None, args)
+            };
+
+            args.tz_offset = -5;
+            assert_eq!(
+                format(&inputs[0], &args),
+                "1999-01-08 07:04:37.000000-05:00"
+            );
+
+            args.timezone = Some("+05:45".parse().unwrap());
+            assert_eq!(
+                format(&inputs[0], &args),
+                "1999-01-08 17:49:37.000000+05:45"
+            );
+
+            args.timezone = Some("America/New_York".parse().unwrap());
+            assert_eq!(
+                format(&inputs[0], &args),
+                "1999-01-08 07:04:37.000000-05:00"
+            );
+            assert_eq!(
+                format(&inputs[1], &args),
+                "2021-07-01 08:00:00.000000-04:00"
+            );
+        }
+
+        #[test]
+        fn test_fractional_seconds() {
+            let micros: i64 = ((3 * 60 + 11) * 60 + 52) * 1_000_000 + 350_000;
+            let bytes = micros.to_le_bytes();
+
+            let mut args = Args::with_defaults();
+
+            let format = |column_type: ColumnType, precision: Option<u32>, args: &Args| {
+                column_type.format_value(Some(&bytes), &None, precision, args)
+            };
+
+            assert_eq!(format(ColumnType::Time, None, &args), "03:11:52.350000");
+            assert_eq!(format(ColumnType::Time, Some(6), &args), "03:11:52.350000");
+            assert_eq!(format(ColumnType::Time, Some(1), &args), "03:11:52.3");
+            assert_eq!(format(ColumnType::Time, Some(0), &args), "03:11:52");
+            assert_eq!(
+                format(ColumnType::Timestamp, Some(3), &args),
+                "2000-01-01 03:11:52.350"
+            );
+            assert_eq!(
+                format(ColumnType::TimestampTz, None, &args),
+                "2000-01-01 03:11:52.350000+00:00"
+            );
+            assert_eq!(format(ColumnType::Interval, None, &args), "03:11:52.350000");
+            assert_eq!(format(ColumnType::Interval, Some(2), &args), "03:11:52.35");
+
+            args.fractional_digits = Some(4);
+
+            assert_eq!(format(ColumnType::Time, Some(0), &args), "03:11:52.3500");
+            assert_eq!(
+                format(ColumnType::TimestampTz, None, &args),
+                "2000-01-01 03:11:52.3500+00:00"
+            );
+
+            let whole_second = 1_000_000i64.to_le_bytes();
+            let output = ColumnType::Time.format_value(Some(&whole_second), &None, Some(3), &args);
+            assert_eq!(output, "00:00:01.0000");
+        }
+
+        #[test]
+        fn test_special_dates() {
+            let args = Args::with_defaults();
+
+            let format = |column_type: &ColumnType, value: i64| {
+                column_type.format_value(Some(&value.to_le_bytes()), &None, None, &args)
+            };
+
+            for column_type in &[
+                ColumnType::Date,
+                ColumnType::Timestamp,
+                ColumnType::TimestampTz,
+            ] {
+                assert_eq!(format(column_type, i64::MAX), "infinity");
+                assert_eq!(format(column_type, i64::MIN + 1), "-infinity");
+                assert_eq!(format(column_type, i64::MIN), "-infinity");
+            }
+
+            // 15 March 44 BC, and 1 January 1 BC, which comes right before 1 January 1 AD.
+            let ides = NaiveDate::from_ymd(-43, 3, 15);
+            let one_bc = NaiveDate::from_ymd(0, 1, 1);
+
+            assert_eq!(
+                format(&ColumnType::Date, encode_date(ides)),
+                "0044-03-15 BC"
+            );
+            assert_eq!(
+                format(&ColumnType::Date, encode_date(one_bc)),
+                "0001-01-01 BC"
+            );
+            assert_eq!(
+                format(
+                    &ColumnType::Timestamp,
+                    encode_timestamp(ides.and_hms(12, 0, 0))
+                ),
+                "0044-03-15 12:00:00.000000 BC"
+            );
+            assert_eq!(
+                format(
+                    &ColumnType::TimestampTz,
+                    encode_timestamp(ides.and_hms(12, 0, 0))
+                ),
+                "0044-03-15 12:00:00.000000+00:00 BC"
+            );
+
+            // Too far away for chrono
+            assert_eq!(format(&ColumnType::Date, 1_000_000_000), "2739907-01-04");
+            assert_eq!(
+                format(&ColumnType::Date, -1_000_000_000),
+                "2735909-12-28 BC"
+            );
+            assert_eq!(
+                format(&ColumnType::Timestamp, 100_000_000 * 86_400_000_000 + 1_500),
+                "275790-09-13 00:00:00.001500"
+            );
+            assert_eq!(
+                format(&ColumnType::TimestampTz, i64::MAX - 1),
+                "294277-01-09 04:00:54.775806+00:00"
+            );
+        }
+
+        #[test]
+        fn test_civil_from_days() {
+            let epoch = NaiveDate::from_ymd(2000, 1, 1);
+
+            for days in (-1_000_000..1_000_000).step_by(997) {
+                let date = epoch + Duration::days(days);
+
+                assert_eq!(
+                    civil_from_days(days),
+                    (date.year() as i64, date.month(), date.day()),
+                    "{}",
+                    days
+                );
+            }
+        }
+
+        #[test]
+        fn test_time_format() {
+            let micros: i64 = ((3 * 60 + 11) * 60 + 52) * 1_000_000 + 350_000;
+            let bytes = micros.to_le_bytes();
+
+            let mut args = Args::with_defaults();
+            args.time_format = Some("epoch_ms".parse().unwrap());
+
+            let format =
+                |column_type: ColumnType, conversion: &Option<ColumnConversion>, args: &Args| {
+                    column_type.format_value(Some(&bytes), conversion, None, args)
+                };
+
+            assert_eq!(format(ColumnType::Timestamp, &None, &args), "946696312350");
+            assert_eq!(format(ColumnType::Time, &None, &args), "11512350");
+            assert_eq!(
+                format(ColumnType::Interval, &None, &args),
+                "03:11:52.350000"
+            );
+
+            // The column's own format is used before the one from the command line.
+            let column_format = Some(ColumnConversion::Format("%d/%m/%Y %H:%M".parse().unwrap()));
+            assert_eq!(
+                format(ColumnType::Timestamp, &column_format, &args),
+                "01/01/2000 03:11"
+            );
+
+            args.time_format = Some("rfc3339".parse().unwrap());
+            args.timezone = Some("+05:30".parse().unwrap());
+
+            assert_eq!(
+                format(ColumnType::Timestamp, &None, &args),
+                "2000-01-01T03:11:52.350000Z"
+            );
+            assert_eq!(
+                format(ColumnType::TimestampTz, &None, &args),
+                "2000-01-01T08:41:52.350000+05:30"
+            );
+
+            let date = 31i64.to_le_bytes();
+            let output = ColumnType::Date.format_value(Some(&date), &None, None, &args);
+            assert_eq!(output, "2000-02-01T00:00:00Z");
+        }
+
         #[test]
         fn test_time() {
             let column_type = ColumnType::Time;
@@ -712,62 +1400,128 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
-                assert_eq!(expected_output, output);
+                assert_eq!(format!("{}.000000", expected_output), output);
             }
         }
 
         // TODO: I need to think more about how to test this one. It does work
         // correctly against `data/all-types.bin`.
-        // #[test]
-        // fn test_timetz() {
-        //     let column_type = ColumnType::TimeTz;
-        //     let midnight = NaiveTime::from_hms_nano(0, 0, 0, 0);
-        //
-        //     let expected_outputs = vec!["05:30:15", "11:22:33", "17:15:16"];
-        //     let inputs: Vec<i64> = expected_outputs
-        //         .iter()
-        //         .map(|time_str| {
-        //             let time = NaiveTime::parse_from_str(time_str, "%H:%M:%S").unwrap();
-        //             let diff = time - midnight;
-        //             diff.num_microseconds().unwrap()
-        //         })
-        //         .collect();
-        //
-        //     let u_inputs = vec_i_into_u::<i64, u64>(inputs);
-        //
-        //     for (input, expected_output) in u_inputs.iter().zip(expected_outputs) {
-        //         let byte_vec = input.to_le_bytes().to_vec();
-        //         let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
-        //
-        //         let output = column_type.format_value(&byte_vec_option, 0, &None);
-        //
-        //         assert_eq!(expected_output, output);
-        //     }
-        // }
+        #[test]
+        fn test_timetz() {
+            let column_type = ColumnType::TimeTz;
+            let args = Args::with_defaults();
+
+            let inputs = [
+                ("15:12:34", -5 * 3600),
+                ("15:12:34", 0),
+                ("09:30:00", 5 * 3600 + 30 * 60),
+                ("23:15:00", 5 * 3600 + 45 * 60),
+                ("01:00:00", -(3 * 3600 + 30 * 60)),
+                ("00:00:00.250000", 9 * 3600 + 30 * 60),
+                ("12:00:00", 14 * 3600),
+                ("12:00:00", -12 * 3600),
+            ];
+
+            let expected_outputs = vec![
+                "15:12:34.000000-05:00",
+                "15:12:34.000000+00:00",
+                "09:30:00.000000+05:30",
+                "23:15:00.000000+05:45",
+                "01:00:00.000000-03:30",
+                "00:00:00.250000+09:30",
+                "12:00:00.000000+14:00",
+                "12:00:00.000000-12:00",
+            ];
+
+            for ((time, offset), expected_output) in inputs.iter().zip(expected_outputs) {
+                let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").unwrap();
+                let bytes = encode_time_tz(time, *offset).to_le_bytes();
+
+                assert_eq!(
+                    column_type.decode(Some(&bytes)),
+                    ColumnValue::TimeTz(time - Duration::seconds(*offset as i64), *offset)
+                );
+
+                let output = column_type.format_value(Some(&bytes), &None, None, &args);
+
+                assert_eq!(output, expected_output);
+            }
+        }
+
+        #[test]
+        fn test_timetz_from_vertica() {
+            // 15:12:34-05, from Vertica's own example file
+            let bytes = [0xD0, 0x97, 0x01, 0x80, 0xF0, 0x79, 0xF0, 0x10];
+            let output =
+                ColumnType::TimeTz.format_value(Some(&bytes), &None, None, &Args::with_defaults());
+
+            assert_eq!(output, "15:12:34.000000-05:00");
+        }
 
         #[test]
         fn test_binary() {
             let column_type = ColumnType::Binary;
 
             let inputs: Vec<i64> = vec![1, 10, 123, 808080];
-            let expected_outputs = vec!["0x1", "0xA", "0x7B", "0x9054C"];
+            let expected_outputs = vec!["0x01", "0x0A", "0x7B", "0x90540C"];
             let u_inputs = vec_i_into_u::<i64, u64>(inputs);
 
             let mut args = Args::with_defaults();
             args.hex_prefix = true;
+            args.trim_binary = true;
 
             for (input, expected_output) in u_inputs.iter().zip(expected_outputs) {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(output, expected_output);
             }
         }
 
+        #[test]
+        fn test_binary_keeps_zero_bytes() {
+            let bytes: &[u8] = &[0x00, 0x0A, 0x00, 0x0B, 0x00, 0x00];
+
+            let mut args = Args::with_defaults();
+
+            let output = ColumnType::Binary.format_value(Some(bytes), &None, None, &args);
+            assert_eq!(output, "000A000B0000");
+
+            args.trim_binary = true;
+
+            let output = ColumnType::Binary.format_value(Some(bytes), &None, None, &args);
+            assert_eq!(output, "000A000B");
+
+            let output = ColumnType::Varbinary.format_value(Some(bytes), &None, None, &args);
+            assert_eq!(output, "000A000B0000");
+
+            args.binary_encoding = BinaryEncoding::OctalEscape;
+
+            let output = ColumnType::Varbinary.format_value(Some(bytes), &None, None, &args);
+            assert_eq!(output, "\\000\\012\\000\\013\\000\\000");
+        }
+
+        #[test]
+        fn test_addresses_keep_zero_bytes() {
+            let args = Args::with_defaults();
+
+            let ip = Some(ColumnConversion::IpAddress);
+            let bytes: &[u8] = &[0xFF, 0xFF, 0x0A, 0x00, 0x00, 0x01, 0x00, 0x00];
+            let output = ColumnType::Binary.format_value(Some(bytes), &ip, None, &args);
+            assert_eq!(output, "10.0.0.1");
+
+            let mac = Some(ColumnConversion::MacAddress);
+            let bytes: &[u8] = &[0xF4, 0x00, 0x1B, 0x28, 0x00, 0x4C];
+            let output = ColumnType::Varbinary.format_value(Some(bytes), &mac, None, &args);
+            assert_eq!(output, "F4:00:1B:28:00:4C");
+        }
+
         #[test]
         fn test_numeric() {
             let column_type = ColumnType::Numeric;
@@ -782,7 +1536,8 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
                 assert_eq!(output, expected_output);
             }
@@ -812,9 +1567,84 @@ mod tests {
                 let byte_vec = input.to_le_bytes().to_vec();
                 let byte_vec_option: Option<Vec<u8>> = Some(byte_vec);
 
-                let output = column_type.format_value(&byte_vec_option, 0, &None, &args);
+                let output =
+                    column_type.format_value(byte_vec_option.as_deref(), &None, None, &args);
 
-                assert_eq!(expected_output, output);
+                assert_eq!(format!("{}.000000", expected_output), output);
+            }
+        }
+
+        #[test]
+        fn test_interval_day_to_second() {
+            let column_type = ColumnType::Interval;
+            let day: i64 = 86_400_000_000;
+            let hour: i64 = 3_600_000_000;
+
+            let cases = vec![
+                (0, "00:00:00.000000", "PT0S"),
+                (500_000, "00:00:00.500000", "PT0.5S"),
+                (
+                    3 * hour + 3 * 60_000_000 + 3_000_000,
+                    "03:03:03.000000",
+                    "PT3H3M3S",
+                ),
+                (day, "1 00:00:00.000000", "P1D"),
+                (
+                    100 * day + 2 * hour + 4_000_250,
+                    "100 02:00:04.000250",
+                    "P100DT2H4.00025S",
+                ),
+                (-(day + hour), "-1 01:00:00.000000", "-P1DT1H"),
+                (-1, "-00:00:00.000001", "-PT0.000001S"),
+            ];
+
+            let mut args = Args::with_defaults();
+
+            for (input, vertica, iso) in cases {
+                let bytes = input.to_le_bytes();
+
+                args.interval_format = IntervalFormat::Vertica;
+                assert_eq!(
+                    column_type.format_value(Some(&bytes), &None, None, &args),
+                    vertica
+                );
+
+                args.interval_format = IntervalFormat::Iso8601;
+                assert_eq!(
+                    column_type.format_value(Some(&bytes), &None, None, &args),
+                    iso
+                );
+            }
+        }
+
+        #[test]
+        fn test_interval_year_to_month() {
+            let column_type = ColumnType::IntervalYearToMonth;
+
+            let cases = vec![
+                (0i64, "0-00", "P0M"),
+                (14, "1-02", "P1Y2M"),
+                (24, "2-00", "P2Y"),
+                (11, "0-11", "P11M"),
+                (-27, "-2-03", "-P2Y3M"),
+            ];
+
+            let mut args = Args::with_defaults();
+
+            for (input, vertica, iso) in cases {
+                let bytes = input.to_le_bytes();
+
+                args.interval_format = IntervalFormat::Vertica;
+                assert_eq!(
+                    column_type.format_value(Some(&bytes), &None, None, &args),
+                    vertica
+                );
+
+                args.interval_format = IntervalFormat::Iso8601;
+                assert_eq!(
+                    column_type.format_value(Some(&bytes), &None, None, &args),
+                    iso
+                );
             }
         }
 
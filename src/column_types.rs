@@ -1,6 +1,10 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
+use anyhow::bail;
+use lazy_static::lazy_static;
+use regex::Regex;
+
 use crate::column_conversion::ColumnConversion;
 use crate::column_type::ColumnType;
 
@@ -10,6 +14,9 @@ pub struct ColumnTypes {
     pub column_types: Vec<ColumnType>,
     pub column_names: Vec<String>,
     pub column_conversions: Vec<Option<ColumnConversion>>,
+    /// The numbers in parentheses after each type, like the length in `varchar(20)`, or the
+    /// precision and scale in `numeric(10,2)`; empty if there aren't any
+    pub column_parameters: Vec<Vec<u32>>,
 }
 
 impl ColumnTypes {
@@ -17,53 +24,130 @@ impl ColumnTypes {
         let mut column_types: Vec<ColumnType> = vec![];
         let mut column_names: Vec<String> = vec![];
         let mut column_conversions: Vec<Option<ColumnConversion>> = vec![];
+        let mut column_parameters: Vec<Vec<u32>> = vec![];
 
         let buf = BufReader::new(reader);
 
         // Loop over all the rows of the types file, skipping blank lines.
-        for line in buf
-            .lines()
-            .filter(|l| l.is_ok() && !l.as_ref().unwrap().is_empty())
-        {
-            if let Ok(line) = line {
-                let chunks: Vec<String> = line.split("/").map(|s| s.to_string()).collect();
-
-                // We know the column type is there
-                let column_type = ColumnType::from_string(&chunks[0].trim())?;
-
-                // Column name is optional, so we'll use a blank if it's not there
-                let column_name = if chunks.len() > 1 {
-                    chunks[1].trim().to_string()
-                } else {
-                    "".to_string()
-                };
-
-                // The column converter is also optional
-                let column_conversion = if chunks.len() > 2 {
-                    match ColumnConversion::from_string(chunks[2].trim()) {
-                        Ok(column_conversion) => Some(column_conversion),
-                        Err(_) => None,
-                    }
-                } else {
-                    None
-                };
+        for line in buf.lines().map_while(Result::ok).filter(|l| !l.is_empty()) {
+            // A date or time format can have slashes in it, like `%m/%d/%Y`, so the third
+            // chunk is everything after the second slash.
+            let chunks: Vec<String> = line.splitn(3, '/').map(|s| s.to_string()).collect();
 
-                column_types.push(column_type);
-                column_names.push(column_name);
-                column_conversions.push(column_conversion);
+            // We know the column type is there
+            let column_type = ColumnType::from_string(chunks[0].trim())?;
+            let parameters = parse_parameters(chunks[0].trim())?;
+
+            if column_type.has_precision() && parameters.iter().any(|p| *p > 6) {
+                bail!(
+                    "invalid precision: {}; it can be from 0 to 6",
+                    chunks[0].trim()
+                );
             }
+
+            // Column name is optional, so we'll use a blank if it's not there
+            let column_name = if chunks.len() > 1 {
+                chunks[1].trim().to_string()
+            } else {
+                "".to_string()
+            };
+
+            // The column converter is also optional. For dates, times, and booleans, it's a format.
+            let column_conversion = match chunks.get(2).map(|c| c.trim()) {
+                None | Some("") => None,
+                Some(format) if column_type.is_temporal() => match format.parse() {
+                    Ok(format) => Some(ColumnConversion::Format(format)),
+                    Err(e) => bail!("{}", e),
+                },
+                Some(format) if column_type == ColumnType::Boolean => match format.parse() {
+                    Ok(format) => Some(ColumnConversion::BoolFormat(format)),
+                    Err(e) => bail!("{}", e),
+                },
+                Some(conversion) => ColumnConversion::from_string(conversion).ok(),
+            };
+
+            column_types.push(column_type);
+            column_names.push(column_name);
+            column_conversions.push(column_conversion);
+            column_parameters.push(parameters);
         }
 
         Ok(ColumnTypes {
             column_types,
             column_names,
             column_conversions,
+            column_parameters,
         })
     }
 
+    /// The precision of a time, timestamp, or interval column, which is how many digits of
+    /// fractional seconds it has, like the 3 in `timestamp(3)`, or `None` if it wasn't given.
+    ///
+    /// * `index` - the column's index, counting from 0
+    pub fn precision(&self, index: usize) -> Option<u32> {
+        if self.column_types[index].has_precision() {
+            self.column_parameters[index].first().copied()
+        } else {
+            None
+        }
+    }
+
     pub fn has_names(&self) -> bool {
-        return self.column_names.iter().all(|n| n != "");
+        self.column_names.iter().all(|n| !n.is_empty())
     }
+
+    /// Work out which columns to output, and in what order. Each entry in `columns` is either
+    /// the name of a column, or its position, counting from 1. Names are checked first, so a
+    /// column that happens to be named `3` can still be selected by name. If `columns` is
+    /// empty, every column is selected, in the order they appear in the file.
+    ///
+    /// * `columns` - the names or positions of the columns to select
+    pub fn select(&self, columns: &[String]) -> anyhow::Result<Vec<usize>> {
+        if columns.is_empty() {
+            return Ok((0..self.column_types.len()).collect());
+        }
+
+        let mut selected: Vec<usize> = vec![];
+
+        for column in columns.iter().map(|c| c.trim()) {
+            let index = match self.column_names.iter().position(|n| n == column) {
+                Some(index) => index,
+                None => match column.parse::<usize>() {
+                    Ok(position) if position >= 1 && position <= self.column_types.len() => {
+                        position - 1
+                    }
+                    Ok(_) => bail!("column number out of range: {}", column),
+                    Err(_) => bail!("no such column: {}", column),
+                },
+            };
+
+            selected.push(index);
+        }
+
+        Ok(selected)
+    }
+}
+
+/// Pull the numbers out of the parentheses after a type, if there are any.
+///
+/// * `string` - the type, like `numeric(10, 2)`
+fn parse_parameters(string: &str) -> anyhow::Result<Vec<u32>> {
+    lazy_static! {
+        static ref PARAMETERS_REGEX: Regex = Regex::new(r"\((.+)\)").unwrap();
+    }
+
+    let parameters = match PARAMETERS_REGEX.captures(string) {
+        Some(captures) => captures[1].to_string(),
+        None => return Ok(vec![]),
+    };
+
+    parameters
+        .split(',')
+        .map(|p| match p.trim().parse::<u32>() {
+            Ok(p) => Ok(p),
+            Err(_) => bail!("invalid type parameters: {}", string),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -71,7 +155,7 @@ mod tests {
     use std::io::BufReader;
 
     use crate::column_type::ColumnType::*;
-    use crate::column_types::ColumnTypes;
+    use crate::column_types::{parse_parameters, ColumnTypes};
 
     #[test]
     fn test_good_input() {
@@ -101,6 +185,115 @@ mod tests {
         assert_eq!(expected_types, column_types.column_types)
     }
 
+    #[test]
+    fn test_select_columns() {
+        use std::fs::File;
+
+        let file = BufReader::new(File::open("data/all-valid-types-with-names.txt").unwrap());
+        let column_types = ColumnTypes::from_reader(file).unwrap();
+
+        assert_eq!(
+            (0..14).collect::<Vec<usize>>(),
+            column_types.select(&[]).unwrap()
+        );
+
+        let columns = vec!["The_Date".to_string(), "1".to_string(), "VB3".to_string()];
+        assert_eq!(vec![5, 0, 10], column_types.select(&columns).unwrap());
+
+        assert!(column_types.select(&["15".to_string()]).is_err());
+        assert!(column_types.select(&["0".to_string()]).is_err());
+        assert!(column_types.select(&["NoSuchColumn".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert_eq!(parse_parameters("varchar").unwrap(), Vec::<u32>::new());
+        assert_eq!(parse_parameters("varchar(20)").unwrap(), vec![20]);
+        assert_eq!(parse_parameters("numeric(10, 2)").unwrap(), vec![10, 2]);
+        assert!(parse_parameters("char(ten)").is_err());
+    }
+
+    #[test]
+    fn test_precision() {
+        use std::env::temp_dir;
+        use std::fs::{self, File};
+
+        use uuid::Uuid;
+
+        let file = BufReader::new(File::open("data/precision-types.txt").unwrap());
+        let column_types = ColumnTypes::from_reader(file).unwrap();
+
+        let precisions: Vec<Option<u32>> = (0..5).map(|i| column_types.precision(i)).collect();
+        assert_eq!(precisions, vec![Some(3), Some(0), None, Some(6), None]);
+
+        let path = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        fs::write(&path, "Timestamp(7)\n").unwrap();
+
+        let result = ColumnTypes::from_reader(BufReader::new(File::open(&path).unwrap()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_column_formats() {
+        use std::env::temp_dir;
+        use std::fs::{self, File};
+
+        use uuid::Uuid;
+
+        use crate::args::BoolFormat;
+        use crate::column_conversion::ColumnConversion;
+        use crate::time_format::TimeFormat;
+
+        let file = BufReader::new(File::open("data/column-format-types.txt").unwrap());
+        let column_types = ColumnTypes::from_reader(file).unwrap();
+
+        let formats: Vec<Option<&TimeFormat>> = column_types
+            .column_conversions
+            .iter()
+            .map(|conversion| match conversion {
+                Some(ColumnConversion::Format(format)) => Some(format),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            formats,
+            vec![
+                Some(&TimeFormat::Strftime("%m/%d/%Y".to_string())),
+                Some(&TimeFormat::EpochMilliseconds),
+                None,
+                None,
+                None,
+            ]
+        );
+        assert!(matches!(
+            column_types.column_conversions[3],
+            Some(ColumnConversion::IpAddress)
+        ));
+        assert!(matches!(
+            column_types.column_conversions[4],
+            Some(ColumnConversion::BoolFormat(BoolFormat::Letters))
+        ));
+
+        let path = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        fs::write(&path, "Date/Day/day-month-year\n").unwrap();
+
+        let result = ColumnTypes::from_reader(BufReader::new(File::open(&path).unwrap()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+
+        let path = temp_dir().join(format!("{}.txt", Uuid::new_v4()));
+        fs::write(&path, "Boolean/Active/yes/no\n").unwrap();
+
+        let result = ColumnTypes::from_reader(BufReader::new(File::open(&path).unwrap()));
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
     #[test]
     #[should_panic]
     fn test_invalid_input() {
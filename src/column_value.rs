@@ -0,0 +1,41 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A single value from a row, decoded from its raw bytes into the matching Rust type, but not
+/// yet formatted for output. `ColumnType::decode` creates these. Values that are just text or
+/// bytes in the file borrow them from the row, rather than copying them.
+///
+pub enum ColumnValue<'a> {
+    Null,
+    Integer(i64),
+    Float(f64),
+    /// A `char` or `varchar`, with any padding trimmed
+    String(&'a str),
+    Boolean(bool),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    /// The instant, in UTC
+    TimestampTz(NaiveDateTime),
+    Time(NaiveTime),
+    /// The time of day in UTC, and the zone's offset, in seconds east of UTC; add them for
+    /// the local time
+    TimeTz(NaiveTime, i32),
+    /// A `binary` or `varbinary`
+    Binary(&'a [u8]),
+    /// The raw bytes of a `numeric`; a sequence of little-endian `u64` words, most significant
+    /// word first
+    Numeric(&'a [u8]),
+    /// The length of the interval, in microseconds
+    Interval(i64),
+    /// The length of the interval, in months
+    IntervalYearToMonth(i64),
+    /// A `date` or `timestamp` of `infinity`, which is later than all others
+    Infinity,
+    /// A `date` or `timestamp` of `-infinity`, which is earlier than all others
+    NegativeInfinity,
+    /// A `date`, in days, or a `timestamp`, in microseconds, since 2000-01-01, that's too far
+    /// from it for chrono
+    OutOfRange(i64),
+    Uuid(Uuid),
+}
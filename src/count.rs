@@ -0,0 +1,216 @@
+use std::io::{stdout, Write};
+
+use anyhow::Context;
+use serde_json::json;
+
+use crate::args::CountArgs;
+use crate::vertica_native_file::{is_null, VerticaNativeFile};
+use crate::{check_column_count, read_types_file, InputFile};
+
+/// The result of counting the rows of a native file.
+#[derive(Debug, PartialEq)]
+pub struct RowCount {
+    pub rows: usize,
+    /// The number of nulls in each column, if they were asked for
+    pub nulls: Option<Vec<usize>>,
+}
+
+/// Run the `count` subcommand, writing the counts to `stdout`.
+///
+/// * `args` - the subcommand's arguments
+pub fn count_file(args: &CountArgs) -> anyhow::Result<()> {
+    let mut input_file = InputFile::open(&args.input, args.mmap)?;
+    let mut native_file = input_file.native_file().context("creating file")?;
+
+    // The types file is only used for the column names, so all that matters is that it has the
+    // right number of columns.
+    let names = match &args.types {
+        Some(path) => {
+            let types = read_types_file(path)?;
+            check_column_count(&types, native_file.definitions.column_widths.len())?;
+
+            types.column_names
+        }
+        None => vec![],
+    };
+
+    let count = count_rows(&mut native_file, args.nulls)?;
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    if args.is_json {
+        count.write_json(&mut writer, &names)
+    } else {
+        count.write_text(&mut writer, &names)
+    }
+}
+
+/// Count the rows of the file, stepping over each one using its length. None of the columns
+/// are looked at; if `nulls` is set, the null bitfield at the start of each row is read, but
+/// that's all. A row that runs past the end of the file is an error, since a count that's
+/// silently short is worse than no count at all.
+///
+/// * `native_file` - the file, positioned at its first row
+/// * `nulls` - whether to count the nulls in each column
+pub fn count_rows(native_file: &mut VerticaNativeFile, nulls: bool) -> anyhow::Result<RowCount> {
+    let column_count = native_file.definitions.column_widths.len();
+
+    let mut rows: usize = 0;
+    let mut null_counts = vec![0; column_count];
+
+    loop {
+        let bitfield = match native_file.next_bitfield()? {
+            Some(bitfield) => bitfield,
+            None => break,
+        };
+
+        rows += 1;
+
+        if nulls {
+            for (index, count) in null_counts.iter_mut().enumerate() {
+                if is_null(bitfield, index) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    Ok(RowCount {
+        rows,
+        nulls: if nulls { Some(null_counts) } else { None },
+    })
+}
+
+impl RowCount {
+    /// Write the counts as text. Columns are named from `names`, or numbered, counting from 1,
+    /// if there are no names.
+    pub fn write_text(&self, writer: &mut impl Write, names: &[String]) -> anyhow::Result<()> {
+        writeln!(writer, "Rows:  {}", self.rows)?;
+
+        if let Some(nulls) = &self.nulls {
+            let labels: Vec<String> = (0..nulls.len()).map(|i| label(names, i)).collect();
+            let width = labels.iter().map(|l| l.len()).max().unwrap_or(0).max(6);
+
+            writeln!(writer)?;
+            writeln!(writer, "{:<width$}  Nulls", "Column", width = width)?;
+
+            for (label, count) in labels.iter().zip(nulls) {
+                writeln!(writer, "{:<width$}  {}", label, count, width = width)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the counts as a JSON object. Each column's null count has its position, counting
+    /// from 1, and its name, if there is one.
+    pub fn write_json(&self, writer: &mut impl Write, names: &[String]) -> anyhow::Result<()> {
+        let mut value = json!({ "rows": self.rows });
+
+        if let Some(nulls) = &self.nulls {
+            value["nulls"] = nulls
+                .iter()
+                .enumerate()
+                .map(|(index, count)| {
+                    json!({
+                        "column": index + 1,
+                        "name": names.get(index).filter(|n| !n.is_empty()),
+                        "nulls": count,
+                    })
+                })
+                .collect();
+        }
+
+        serde_json::to_writer_pretty(&mut *writer, &value)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
+
+/// The name of the column at `index`, or its position, if it doesn't have a name.
+fn label(names: &[String], index: usize) -> String {
+    match names.get(index) {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => (index + 1).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use crate::count::{count_rows, RowCount};
+    use crate::vertica_native_file::VerticaNativeFile;
+
+    fn count(file_name: &str, nulls: bool) -> RowCount {
+        let mut reader = BufReader::new(File::open(file_name).unwrap());
+        let mut native_file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+
+        count_rows(&mut native_file, nulls).unwrap()
+    }
+
+    #[test]
+    fn test_count_rows() {
+        assert_eq!(count("data/all-types.bin", false).rows, 1);
+        assert_eq!(count("data/all-types-ten-rows.bin", false).rows, 10);
+        assert_eq!(count("data/all-types.bin", false).nulls, None);
+
+        let bytes = std::fs::read("data/all-types-ten-rows.bin").unwrap();
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+
+        assert_eq!(count_rows(&mut native_file, true).unwrap().rows, 10);
+    }
+
+    #[test]
+    fn test_count_nulls() {
+        let count = count("data/all-types-with-nulls.bin", true);
+
+        let mut expected = vec![0; 14];
+        expected[1] = 1;
+
+        assert_eq!(count.rows, 1);
+        assert_eq!(count.nulls, Some(expected));
+    }
+
+    #[test]
+    fn test_count_truncated_file() {
+        let bytes = std::fs::read("data/all-types-ten-rows.bin").unwrap();
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes[..bytes.len() - 10]).unwrap();
+
+        let res = count_rows(&mut native_file, false);
+
+        assert!(res.is_err());
+        assert!(res
+            .unwrap_err()
+            .to_string()
+            .starts_with("row 10 at offset "));
+    }
+
+    #[test]
+    fn test_count_output() {
+        let count = count("data/all-types-with-nulls.bin", true);
+        let names: Vec<String> = vec![];
+
+        let mut text: Vec<u8> = vec![];
+        count.write_text(&mut text, &names).unwrap();
+        let text = String::from_utf8(text).unwrap();
+
+        assert!(text.starts_with("Rows:  1\n\nColumn  Nulls\n1       0\n2       1\n"));
+
+        let names: Vec<String> = (1..=14).map(|i| format!("Col{}", i)).collect();
+
+        let mut json: Vec<u8> = vec![];
+        count.write_json(&mut json, &names).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["rows"], 1);
+        assert_eq!(json["nulls"][1]["column"], 2);
+        assert_eq!(json["nulls"][1]["name"], "Col2");
+        assert_eq!(json["nulls"][1]["nulls"], 1);
+    }
+}
@@ -0,0 +1,795 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{stdout, BufReader, Write};
+
+use anyhow::{bail, Context};
+use serde_json::json;
+
+use crate::args::{Args, DiffArgs};
+use crate::column_types::ColumnTypes;
+use crate::vertica_native_file::{OwnedRow, Row, VerticaNativeFile};
+use crate::{check_column_count, read_types_file};
+
+/// How a row differs between the two files.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// The row is only in the second file
+    Added,
+    /// The row is only in the first file
+    Removed,
+    /// The row is in both files, but some of its values are different
+    Changed,
+}
+
+/// A value that's different in the two files. Values are formatted the same way they are when
+/// converting, with `None` for nulls, so a null is never mistaken for the text `null`. For an
+/// added row, every value is only in the second file, so `first` is `None`; for a removed row,
+/// `second` is `None`.
+#[derive(Debug, PartialEq)]
+pub struct Cell {
+    /// The column's index, counting from 0
+    pub column: usize,
+    pub first: Option<Option<String>>,
+    pub second: Option<Option<String>>,
+}
+
+/// The values of a row's key columns, with `None` for nulls.
+pub type Key = Vec<Option<String>>;
+
+/// A row that differs between the two files.
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    pub change: Change,
+    /// The row's number in each file, counting from 1, if it's in that file
+    pub first_row: Option<usize>,
+    pub second_row: Option<usize>,
+    /// The values of the key columns, when rows are matched by key, with `None` for nulls
+    pub key: Option<Key>,
+    pub cells: Vec<Cell>,
+}
+
+/// The result of comparing two native files.
+#[derive(Debug, Default)]
+pub struct DiffReport {
+    /// Differences between the headers
+    pub header: Vec<String>,
+    /// The first few rows that differ
+    pub differences: Vec<Difference>,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub same: usize,
+}
+
+/// Run the `diff` subcommand. The report goes to `stdout`, and if the files differ, an error is
+/// returned, so the program exits with a non-zero status, like `diff` does.
+///
+/// * `args` - the subcommand's arguments
+pub fn diff_files(args: &DiffArgs) -> anyhow::Result<()> {
+    let types = read_types_file(&args.types)?;
+    let key = if args.key.is_empty() {
+        vec![]
+    } else {
+        types.select(&args.key).context("selecting key columns")?
+    };
+
+    let open = |path: &str| match File::open(path) {
+        Ok(file) => Ok(BufReader::new(file)),
+        Err(e) => bail!("opening input file [{}]: {}", path, e),
+    };
+
+    let mut first_reader = open(&args.first)?;
+    let mut second_reader = open(&args.second)?;
+
+    let mut first = VerticaNativeFile::from_reader(&mut first_reader)
+        .with_context(|| format!("reading [{}]", args.first))?;
+    let mut second = VerticaNativeFile::from_reader(&mut second_reader)
+        .with_context(|| format!("reading [{}]", args.second))?;
+
+    check_column_count(&types, first.definitions.column_widths.len())
+        .with_context(|| format!("checking [{}]", args.first))?;
+    check_column_count(&types, second.definitions.column_widths.len())
+        .with_context(|| format!("checking [{}]", args.second))?;
+
+    let report = diff(
+        &mut first,
+        &mut second,
+        [&args.first, &args.second],
+        &types,
+        &key,
+        args.max_differences,
+    )?;
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    if args.is_json {
+        report.write_json(&mut writer, &types)?;
+    } else {
+        report.write_text(&mut writer, &types)?;
+    }
+
+    if report.is_same() {
+        Ok(())
+    } else {
+        bail!("{} and {} differ", args.first, args.second)
+    }
+}
+
+/// Compare two native files, which both have the columns in `types`. The headers are compared
+/// first, and then the rows. Without a key, rows are compared by position: the first row of one
+/// file with the first row of the other, and so on, and any extra rows at the end of either file
+/// are added or removed. With a key, rows are matched by the values of the key columns, wherever
+/// they are in the files; the first file is held in memory to do that.
+///
+/// * `first` - the original file, positioned at its first row
+/// * `second` - the file to compare it with, positioned at its first row
+/// * `names` - the names of the two files, for reporting a row that can't be read
+/// * `types` - the column types
+/// * `key` - the indexes of the key columns, or empty, to compare by position
+/// * `max_differences` - how many differing rows to keep in the report; they're all counted
+pub fn diff(
+    first: &mut VerticaNativeFile,
+    second: &mut VerticaNativeFile,
+    names: [&str; 2],
+    types: &ColumnTypes,
+    key: &[usize],
+    max_differences: usize,
+) -> anyhow::Result<DiffReport> {
+    let args = Args::with_defaults();
+    let mut report = DiffReport::default();
+
+    if first.definitions.version != second.definitions.version {
+        report.header.push(format!(
+            "the version is {} in the first file, but {} in the second",
+            first.definitions.version, second.definitions.version
+        ));
+    }
+
+    let widths = first
+        .definitions
+        .column_widths
+        .iter()
+        .zip(&second.definitions.column_widths);
+
+    for (index, (first_width, second_width)) in widths.enumerate() {
+        if first_width != second_width {
+            report.header.push(format!(
+                "column {} is {} in the first file, but {} in the second",
+                label(types, index),
+                describe_width(*first_width),
+                describe_width(*second_width)
+            ));
+        }
+    }
+
+    let mut compared = Compared {
+        names,
+        types,
+        args: &args,
+        report: &mut report,
+        max_differences,
+    };
+
+    if key.is_empty() {
+        compared.by_position(first, second)?;
+    } else {
+        compared.by_key(first, second, key)?;
+    }
+
+    Ok(report)
+}
+
+/// The state for comparing the rows of two files.
+struct Compared<'a> {
+    names: [&'a str; 2],
+    types: &'a ColumnTypes,
+    args: &'a Args,
+    report: &'a mut DiffReport,
+    max_differences: usize,
+}
+
+impl Compared<'_> {
+    /// Compare the rows in order, row by row.
+    fn by_position(
+        &mut self,
+        first: &mut VerticaNativeFile,
+        second: &mut VerticaNativeFile,
+    ) -> anyhow::Result<()> {
+        let mut row_number: usize = 0;
+
+        loop {
+            row_number += 1;
+
+            let first_row = self.read_row(first, 0)?;
+            let second_row = self.read_row(second, 1)?;
+
+            match (first_row, second_row) {
+                (None, None) => break,
+                (Some(first_row), Some(second_row)) => {
+                    self.compare(&first_row, &second_row, row_number, row_number, None)
+                }
+                (Some(first_row), None) => self.removed(&first_row, row_number, None),
+                (None, Some(second_row)) => self.added(&second_row, row_number, None),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compare the rows matched by their keys. Rows only in the first file are reported as
+    /// removed at the end, in the order they're in the file.
+    fn by_key(
+        &mut self,
+        first: &mut VerticaNativeFile,
+        second: &mut VerticaNativeFile,
+        key: &[usize],
+    ) -> anyhow::Result<()> {
+        let mut first_rows: HashMap<Key, (usize, OwnedRow)> = HashMap::new();
+        let mut row_number: usize = 0;
+
+        while let Some(row) = self.read_row(first, 0)? {
+            row_number += 1;
+            let row_key = self.key_of(&row, key);
+
+            if first_rows.contains_key(&row_key) {
+                bail!(
+                    "key [{}] is in the first file more than once",
+                    join_key(&row_key)
+                );
+            }
+
+            first_rows.insert(row_key, (row_number, row.to_owned_row()));
+        }
+
+        let mut row_number: usize = 0;
+
+        while let Some(row) = self.read_row(second, 1)? {
+            row_number += 1;
+            let row_key = self.key_of(&row, key);
+
+            match first_rows.remove(&row_key) {
+                Some((first_number, first_row)) => self.compare(
+                    &first_row.row(),
+                    &row,
+                    first_number,
+                    row_number,
+                    Some(row_key),
+                ),
+                None => self.added(&row, row_number, Some(row_key)),
+            }
+        }
+
+        let mut removed: Vec<(Key, (usize, OwnedRow))> = first_rows.into_iter().collect();
+        removed.sort_by_key(|(_, (row_number, _))| *row_number);
+
+        for (row_key, (row_number, row)) in removed {
+            self.removed(&row.row(), row_number, Some(row_key));
+        }
+
+        Ok(())
+    }
+
+    /// Read the next row of one of the files, with the file's name on any error.
+    ///
+    /// * `file` - the file to read from
+    /// * `side` - which of the files it is: 0 for the first, and 1 for the second
+    fn read_row<'f>(
+        &self,
+        file: &'f mut VerticaNativeFile,
+        side: usize,
+    ) -> anyhow::Result<Option<Row<'f>>> {
+        match file.read_row() {
+            Ok(row) => Ok(row),
+            Err(e) => bail!("reading [{}]: {}", self.names[side], e),
+        }
+    }
+
+    /// Compare a row from each file, value by value. Values with the same bytes are the same,
+    /// and a null is never the same as a value. Otherwise, they're formatted, so that, say,
+    /// differently padded `char` values still match.
+    fn compare(
+        &mut self,
+        first: &Row,
+        second: &Row,
+        first_row: usize,
+        second_row: usize,
+        key: Option<Key>,
+    ) {
+        let mut cells: Vec<Cell> = vec![];
+
+        for column in 0..self.types.column_types.len() {
+            let first_bytes = first.column(column);
+            let second_bytes = second.column(column);
+
+            if first_bytes == second_bytes {
+                continue;
+            }
+
+            let first_value = self.format(column, first_bytes);
+            let second_value = self.format(column, second_bytes);
+
+            if first_value != second_value {
+                cells.push(Cell {
+                    column,
+                    first: Some(first_value),
+                    second: Some(second_value),
+                });
+            }
+        }
+
+        if cells.is_empty() {
+            self.report.same += 1;
+        } else {
+            self.report.changed += 1;
+            self.record(Difference {
+                change: Change::Changed,
+                first_row: Some(first_row),
+                second_row: Some(second_row),
+                key,
+                cells,
+            });
+        }
+    }
+
+    fn added(&mut self, row: &Row, row_number: usize, key: Option<Key>) {
+        self.report.added += 1;
+
+        let cells = self.cells(row, Change::Added);
+
+        self.record(Difference {
+            change: Change::Added,
+            first_row: None,
+            second_row: Some(row_number),
+            key,
+            cells,
+        });
+    }
+
+    fn removed(&mut self, row: &Row, row_number: usize, key: Option<Key>) {
+        self.report.removed += 1;
+
+        let cells = self.cells(row, Change::Removed);
+
+        self.record(Difference {
+            change: Change::Removed,
+            first_row: Some(row_number),
+            second_row: None,
+            key,
+            cells,
+        });
+    }
+
+    /// Keep the difference, if there's still room for it in the report.
+    fn record(&mut self, difference: Difference) {
+        if self.report.differences.len() < self.max_differences {
+            self.report.differences.push(difference);
+        }
+    }
+
+    /// Every value of a row that's only in one of the files. For an added row, the values go in
+    /// the second side of each cell, and for a removed one, the first.
+    fn cells(&self, row: &Row, change: Change) -> Vec<Cell> {
+        // Only rows that will be shown need their values formatted.
+        if self.report.differences.len() >= self.max_differences {
+            return vec![];
+        }
+
+        (0..self.types.column_types.len())
+            .map(|column| {
+                let value = Some(self.format(column, row.column(column)));
+                let (first, second) = match change {
+                    Change::Removed => (value, None),
+                    _ => (None, value),
+                };
+
+                Cell {
+                    column,
+                    first,
+                    second,
+                }
+            })
+            .collect()
+    }
+
+    /// The values of a row's key columns, with `None` for nulls, so that a null never matches
+    /// a value, even one that's the text `null`.
+    fn key_of(&self, row: &Row, key: &[usize]) -> Key {
+        key.iter()
+            .map(|column| self.format(*column, row.column(*column)))
+            .collect()
+    }
+
+    /// Format a value the same way it is when converting, or `None` if it's null.
+    fn format(&self, column: usize, bytes: Option<&[u8]>) -> Option<String> {
+        bytes.map(|bytes| {
+            self.types.column_types[column].format_value(
+                Some(bytes),
+                &self.types.column_conversions[column],
+                self.types.precision(column),
+                self.args,
+            )
+        })
+    }
+}
+
+impl DiffReport {
+    /// Whether the files are the same, in both their headers and their rows.
+    pub fn is_same(&self) -> bool {
+        self.header.is_empty() && self.added == 0 && self.removed == 0 && self.changed == 0
+    }
+
+    /// Write the report as text. Each differing row is followed by its values; for a changed
+    /// row, just the ones that changed.
+    pub fn write_text(&self, writer: &mut impl Write, types: &ColumnTypes) -> anyhow::Result<()> {
+        for difference in &self.header {
+            writeln!(writer, "header: {}", difference)?;
+        }
+
+        for difference in &self.differences {
+            let rows = match (difference.first_row, difference.second_row) {
+                (Some(first), Some(second)) if first == second => format!("row {}", first),
+                (Some(first), Some(second)) => format!("row {} -> row {}", first, second),
+                (Some(first), None) => format!("row {}", first),
+                (None, Some(second)) => format!("row {}", second),
+                (None, None) => String::new(),
+            };
+
+            let change = match difference.change {
+                Change::Added => "added",
+                Change::Removed => "removed",
+                Change::Changed => "changed",
+            };
+
+            match &difference.key {
+                Some(key) => writeln!(writer, "{}, key [{}]: {}", rows, join_key(key), change)?,
+                None => writeln!(writer, "{}: {}", rows, change)?,
+            }
+
+            for cell in &difference.cells {
+                match (&cell.first, &cell.second) {
+                    (Some(first), Some(second)) => writeln!(
+                        writer,
+                        "    {}: {} -> {}",
+                        label(types, cell.column),
+                        show(first),
+                        show(second)
+                    )?,
+                    (Some(value), None) | (None, Some(value)) => {
+                        writeln!(writer, "    {}: {}", label(types, cell.column), show(value))?
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+
+        let total = self.added + self.removed + self.changed;
+
+        if total > self.differences.len() {
+            writeln!(
+                writer,
+                "(only the first {} of {} differing rows are shown)",
+                self.differences.len(),
+                total
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "{} added, {} removed, {} changed, {} the same",
+            self.added, self.removed, self.changed, self.same
+        )?;
+
+        Ok(())
+    }
+
+    /// Write the report as a JSON object.
+    pub fn write_json(&self, writer: &mut impl Write, types: &ColumnTypes) -> anyhow::Result<()> {
+        let differences: Vec<_> = self
+            .differences
+            .iter()
+            .map(|difference| {
+                let cells: Vec<_> = difference
+                    .cells
+                    .iter()
+                    .map(|cell| {
+                        let mut value = json!({
+                            "column": cell.column + 1,
+                            "name": Some(&types.column_names[cell.column]).filter(|n| !n.is_empty()),
+                        });
+
+                        // A side the row isn't in is left out, so `null` is only ever a null.
+                        if let Some(first) = &cell.first {
+                            value["first"] = json!(first);
+                        }
+
+                        if let Some(second) = &cell.second {
+                            value["second"] = json!(second);
+                        }
+
+                        value
+                    })
+                    .collect();
+
+                json!({
+                    "change": match difference.change {
+                        Change::Added => "added",
+                        Change::Removed => "removed",
+                        Change::Changed => "changed",
+                    },
+                    "first_row": difference.first_row,
+                    "second_row": difference.second_row,
+                    "key": difference.key,
+                    "cells": cells,
+                })
+            })
+            .collect();
+
+        let value = json!({
+            "header": self.header,
+            "differences": differences,
+            "added": self.added,
+            "removed": self.removed,
+            "changed": self.changed,
+            "same": self.same,
+        });
+
+        serde_json::to_writer_pretty(&mut *writer, &value)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
+
+/// A key's values, separated by commas, with nulls shown as `NULL`.
+fn join_key(key: &[Option<String>]) -> String {
+    let values: Vec<&str> = key.iter().map(show).collect();
+
+    values.join(", ")
+}
+
+/// A value for the text report, with a null shown as `NULL`, like SQL shows it.
+fn show(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("NULL")
+}
+
+/// The column's name, or its position, counting from 1, if it doesn't have a name.
+fn label(types: &ColumnTypes, index: usize) -> String {
+    match &types.column_names[index] {
+        name if name.is_empty() => (index + 1).to_string(),
+        name => name.clone(),
+    }
+}
+
+fn describe_width(width: u32) -> String {
+    if width == u32::MAX {
+        "variable-width".to_string()
+    } else {
+        format!("{} bytes wide", width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use crate::assemble::assemble;
+    use crate::column_types::ColumnTypes;
+    use crate::diff::{diff, Cell, Change, DiffReport};
+    use crate::vertica_native_file::VerticaNativeFile;
+
+    const NAMES: [&str; 2] = ["first.bin", "second.bin"];
+
+    fn types() -> ColumnTypes {
+        ColumnTypes::from_reader(BufReader::new(File::open("data/id-and-name.txt").unwrap()))
+            .unwrap()
+    }
+
+    /// Assemble a file with an `Id` and a `Name` column, and a row for each pair
+    fn file(rows: &[(i64, &str)]) -> Vec<u8> {
+        let mut source = String::from("@signature\n@header 8 var\n");
+
+        for (id, name) in rows {
+            source.push_str(&format!("@row\n@nulls\nint64:{} varchar:{}\n", id, name));
+        }
+
+        assemble(&source).unwrap()
+    }
+
+    fn compare(first: &[u8], second: &[u8], key: &[usize]) -> DiffReport {
+        let mut first = VerticaNativeFile::from_bytes(first).unwrap();
+        let mut second = VerticaNativeFile::from_bytes(second).unwrap();
+
+        diff(&mut first, &mut second, NAMES, &types(), key, 10).unwrap()
+    }
+
+    #[test]
+    fn test_same_files() {
+        let rows = file(&[(1, "one"), (2, "two")]);
+        let report = compare(&rows, &rows, &[]);
+
+        assert!(report.is_same());
+        assert_eq!(report.same, 2);
+    }
+
+    #[test]
+    fn test_diff_by_position() {
+        let first = file(&[(1, "one"), (2, "two"), (3, "three")]);
+        let second = file(&[(1, "one"), (2, "TWO"), (3, "three"), (4, "four")]);
+
+        let report = compare(&first, &second, &[]);
+
+        assert_eq!(
+            (report.added, report.removed, report.changed, report.same),
+            (1, 0, 1, 2)
+        );
+
+        assert_eq!(report.differences[0].change, Change::Changed);
+        assert_eq!(report.differences[0].first_row, Some(2));
+        assert_eq!(
+            report.differences[0].cells,
+            vec![Cell {
+                column: 1,
+                first: Some(Some("two".to_string())),
+                second: Some(Some("TWO".to_string())),
+            }]
+        );
+
+        assert_eq!(report.differences[1].change, Change::Added);
+        assert_eq!(report.differences[1].second_row, Some(4));
+        assert_eq!(report.differences[1].cells.len(), 2);
+
+        let report = compare(&second, &first, &[]);
+        assert_eq!((report.added, report.removed), (0, 1));
+    }
+
+    #[test]
+    fn test_diff_by_key() {
+        let first = file(&[(1, "one"), (2, "two"), (3, "three")]);
+        let second = file(&[(4, "four"), (3, "three"), (1, "ONE")]);
+
+        let report = compare(&first, &second, &[0]);
+
+        assert_eq!(
+            (report.added, report.removed, report.changed, report.same),
+            (1, 1, 1, 1)
+        );
+
+        let changed = &report.differences[1];
+        assert_eq!(changed.change, Change::Changed);
+        assert_eq!((changed.first_row, changed.second_row), (Some(1), Some(3)));
+        assert_eq!(changed.key, Some(vec![Some("1".to_string())]));
+
+        let removed = &report.differences[2];
+        assert_eq!(removed.change, Change::Removed);
+        assert_eq!(removed.key, Some(vec![Some("2".to_string())]));
+
+        let duplicates = file(&[(1, "one"), (1, "uno")]);
+        let mut first = VerticaNativeFile::from_bytes(&duplicates).unwrap();
+        let mut second = VerticaNativeFile::from_bytes(&duplicates).unwrap();
+
+        assert!(diff(&mut first, &mut second, NAMES, &types(), &[0], 10).is_err());
+    }
+
+    #[test]
+    fn test_null_is_not_the_text_null() {
+        let with_null = assemble(
+            "@signature\n@header 8 var\n@row\n@nulls 2\nint64:1\n\
+             @row\n@nulls\nint64:2 varchar:two\n",
+        )
+        .unwrap();
+        let with_text = assemble(
+            "@signature\n@header 8 var\n@row\n@nulls\nint64:1 varchar:null\n\
+             @row\n@nulls\nint64:2 varchar:two\n",
+        )
+        .unwrap();
+
+        let report = compare(&with_null, &with_text, &[]);
+
+        assert_eq!((report.changed, report.same), (1, 1));
+        assert_eq!(
+            report.differences[0].cells,
+            vec![Cell {
+                column: 1,
+                first: Some(None),
+                second: Some(Some("null".to_string())),
+            }]
+        );
+
+        let mut text: Vec<u8> = vec![];
+        report.write_text(&mut text, &types()).unwrap();
+        assert!(String::from_utf8(text)
+            .unwrap()
+            .contains("\n    Name: NULL -> null\n"));
+
+        let mut json: Vec<u8> = vec![];
+        report.write_json(&mut json, &types()).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["differences"][0]["cells"][0]["first"], Value::Null);
+        assert_eq!(json["differences"][0]["cells"][0]["second"], "null");
+
+        // A null key doesn't match a key of "null".
+        let report = compare(&with_null, &with_text, &[1]);
+
+        assert_eq!(
+            (report.added, report.removed, report.changed, report.same),
+            (1, 1, 0, 1)
+        );
+        assert_eq!(
+            report.differences[0].key,
+            Some(vec![Some("null".to_string())])
+        );
+        assert_eq!(report.differences[1].key, Some(vec![None]));
+    }
+
+    #[test]
+    fn test_unreadable_row() {
+        let first = file(&[(1, "one"), (2, "two"), (3, "three")]);
+
+        // The second row's `varchar` is longer than the row.
+        let second = assemble(
+            "@signature\n@header 8 var\n@row\n@nulls\nint64:1 varchar:one\n\
+             @row\n@nulls\nint64:2 uint32:1000\n@row\n@nulls\nint64:3 varchar:three\n",
+        )
+        .unwrap();
+
+        for key in &[vec![], vec![0]] {
+            let mut first = VerticaNativeFile::from_bytes(&first).unwrap();
+            let mut second = VerticaNativeFile::from_bytes(&second).unwrap();
+
+            let message = diff(&mut first, &mut second, NAMES, &types(), key, 10)
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                message,
+                "reading [second.bin]: row 2 at offset 48: row length is 12, but column 2 runs past the end of the row"
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff_headers() {
+        let first = file(&[(1, "one")]);
+        let second = assemble("@signature\n@header 8 3\n@row\n@nulls\nint64:1 char(3):one\n");
+
+        let report = compare(&first, &second.unwrap(), &[]);
+
+        assert_eq!(
+            report.header,
+            vec!["column Name is variable-width in the first file, but 3 bytes wide in the second"]
+        );
+        assert_eq!(report.same, 1);
+        assert!(!report.is_same());
+    }
+
+    #[test]
+    fn test_diff_output() {
+        let first = file(&[(1, "one"), (2, "two")]);
+        let second = file(&[(1, "uno"), (2, "two"), (3, "three")]);
+        let report = compare(&first, &second, &[]);
+
+        let mut text: Vec<u8> = vec![];
+        report.write_text(&mut text, &types()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "row 1: changed\n    Name: one -> uno\nrow 3: added\n    Id: 3\n    Name: three\n\
+             1 added, 0 removed, 1 changed, 1 the same\n"
+        );
+
+        let mut json: Vec<u8> = vec![];
+        report.write_json(&mut json, &types()).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["changed"], 1);
+        assert_eq!(json["differences"][0]["change"], "changed");
+        assert_eq!(json["differences"][0]["cells"][0]["name"], "Name");
+        assert_eq!(json["differences"][0]["cells"][0]["second"], "uno");
+        assert_eq!(json["differences"][1]["first_row"], Value::Null);
+        assert!(json["differences"][1]["cells"][0].get("first").is_none());
+        assert_eq!(json["differences"][1]["cells"][0]["second"], "3");
+    }
+}
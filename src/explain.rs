@@ -0,0 +1,397 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{stdout, BufReader, Read, Write};
+
+use anyhow::{bail, Context};
+
+use crate::args::{Args, ExplainArgs};
+use crate::column_types::ColumnTypes;
+use crate::file_signature::{FileSignature, FILE_SIGNATURE_LENGTH};
+use crate::vertica_native_file::{Row, VerticaNativeFile};
+use crate::{check_column_count, read_types_file};
+
+/// How many bytes are shown on each line of the dump
+const BYTES_PER_LINE: usize = 16;
+
+/// The width of a full line of hex bytes
+const HEX_WIDTH: usize = BYTES_PER_LINE * 3 - 1;
+
+/// Run the `explain` subcommand, writing the annotated dump to `stdout`.
+///
+/// * `args` - the subcommand's arguments
+pub fn explain_file(args: &ExplainArgs) -> anyhow::Result<()> {
+    let mut reader = match File::open(&args.input) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => bail!("opening input file [{}]: {}", args.input, e),
+    };
+
+    let types = match &args.types {
+        Some(path) => Some(read_types_file(path)?),
+        None => None,
+    };
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    explain(&mut reader, types.as_ref(), &args.rows, &mut writer)
+}
+
+/// Write an annotated hex dump of the file's header, and then of its rows. Each line shows the
+/// offset of its bytes from the start of the file, the bytes themselves, and what they are.
+///
+/// * `reader` - the file, positioned at its start
+/// * `types` - the column types, for showing each column's type and decoded value; without
+///   them, only the layout of the rows is shown
+/// * `rows` - the numbers of the rows to show, counting from 1, or empty, for all of them
+/// * `writer` - where to write the dump
+pub fn explain(
+    reader: &mut impl Read,
+    types: Option<&ColumnTypes>,
+    rows: &[usize],
+    writer: &mut impl Write,
+) -> anyhow::Result<()> {
+    // The fixed part of the header runs up to and including the column count, which says how
+    // many column widths follow it.
+    let mut header = vec![0; FILE_SIGNATURE_LENGTH + 9];
+    reader
+        .read_exact(&mut header)
+        .context("file is too short to have a header")?;
+
+    let column_count = u16::from_le_bytes(header[18..20].try_into().unwrap()) as usize;
+    header.resize(header.len() + 4 * column_count, 0);
+    reader
+        .read_exact(&mut header[20..])
+        .context("file is too short to have a header")?;
+
+    explain_header(&header, writer)?;
+
+    if let Some(types) = types {
+        check_column_count(types, column_count)?;
+    }
+
+    // The header has been read already, so it's put back in front of the rest of the file.
+    let mut input = (&header[..]).chain(reader);
+    let mut native_file = VerticaNativeFile::from_reader(&mut input)?;
+    let column_widths = native_file.definitions.column_widths.clone();
+
+    let args = Args::with_defaults();
+
+    // The rows are visited in order, so the file only ever has to move forwards.
+    let mut wanted = rows.to_vec();
+    wanted.sort_unstable();
+    wanted.dedup();
+    let mut wanted = wanted.into_iter();
+
+    let mut columns = Vec::with_capacity(column_widths.len());
+    let mut bad_rows: Vec<String> = vec![];
+
+    loop {
+        if !rows.is_empty() {
+            match wanted.next() {
+                Some(row) => native_file.seek_to_row(row)?,
+                None => break,
+            }
+        }
+
+        // The row is copied, so the file can still be asked where it was.
+        let (bitfield, data) = match native_file.next_raw_row()? {
+            Some((bitfield, data)) => (bitfield.to_vec(), data.to_vec()),
+            None => break,
+        };
+        let row_number = native_file.row_number();
+        let row_offset = native_file.row_offset() as usize;
+
+        writeln!(writer)?;
+        writeln!(writer, "Row {}", row_number)?;
+
+        write_line(
+            writer,
+            row_offset,
+            &(data.len() as u32).to_le_bytes(),
+            &format!("row length: {}", data.len()),
+        )?;
+
+        let bits: Vec<String> = bitfield.iter().map(|b| format!("{:08b}", b)).collect();
+        write_line(
+            writer,
+            row_offset + 4,
+            &bitfield,
+            &format!("null bits: {}", bits.join(" ")),
+        )?;
+
+        let data_offset = row_offset + 4 + bitfield.len();
+
+        // A row whose columns don't fit its length can't be split into columns, so it's shown
+        // as it is, with what's wrong with it.
+        let used = match Row::locate_columns(&column_widths, &bitfield, &data, &mut columns) {
+            Ok(used) => used,
+            Err(e) => {
+                write_line(
+                    writer,
+                    data_offset,
+                    &data,
+                    &format!("row data: {} bytes, but {}", data.len(), e),
+                )?;
+                bad_rows.push(row_number.to_string());
+                continue;
+            }
+        };
+
+        for (index, width) in column_widths.iter().enumerate() {
+            let mut label = format!("column {}", index + 1);
+
+            if let Some(name) = types.map(|t| &t.column_names[index]) {
+                if !name.is_empty() {
+                    label = format!("{} ({})", label, name);
+                }
+            }
+
+            let range = match columns[index].clone() {
+                Some(range) => range,
+                None => {
+                    let type_name = types
+                        .map(|t| format!(", {:?}", t.column_types[index]))
+                        .unwrap_or_default();
+                    write_line(
+                        writer,
+                        data_offset,
+                        &[],
+                        &format!("{}: null{}", label, type_name),
+                    )?;
+                    continue;
+                }
+            };
+
+            // A variable-width column has its length just before it.
+            if *width == u32::MAX {
+                write_line(
+                    writer,
+                    data_offset + range.start - 4,
+                    &data[range.start - 4..range.start],
+                    &format!("{} length: {}", label, range.len()),
+                )?;
+            }
+
+            let bytes = &data[range.clone()];
+
+            let description = match types {
+                Some(types) => format!(
+                    "{}: {} bytes, {:?} = {}",
+                    label,
+                    bytes.len(),
+                    types.column_types[index],
+                    types.column_types[index].format_value(
+                        Some(bytes),
+                        &types.column_conversions[index],
+                        types.precision(index),
+                        &args
+                    )
+                ),
+                None => format!("{}: {} bytes", label, bytes.len()),
+            };
+
+            write_line(writer, data_offset + range.start, bytes, &description)?;
+        }
+
+        if used < data.len() {
+            write_line(
+                writer,
+                data_offset + used,
+                &data[used..],
+                &format!(
+                    "{} bytes left over, but the columns should fill the row",
+                    data.len() - used
+                ),
+            )?;
+            bad_rows.push(row_number.to_string());
+        }
+    }
+
+    if !bad_rows.is_empty() {
+        bail!("can't decode row {}", bad_rows.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Write the annotated header. The widths are shown in the same order as the columns.
+fn explain_header(header: &[u8], writer: &mut impl Write) -> anyhow::Result<()> {
+    let signature = FileSignature::from_reader_unchecked(&mut &header[..])?;
+    let u32_at = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+
+    writeln!(writer, "Header")?;
+
+    write_line(
+        writer,
+        0,
+        &header[..11],
+        if signature.is_valid() {
+            "signature (valid)"
+        } else {
+            "signature (invalid)"
+        },
+    )?;
+    write_line(
+        writer,
+        11,
+        &header[11..15],
+        &format!("header length: {}", u32_at(11)),
+    )?;
+    write_line(
+        writer,
+        15,
+        &header[15..17],
+        &format!(
+            "version: {}",
+            u16::from_le_bytes(header[15..17].try_into().unwrap())
+        ),
+    )?;
+    write_line(writer, 17, &header[17..18], "filler")?;
+    write_line(
+        writer,
+        18,
+        &header[18..20],
+        &format!("column count: {}", (header.len() - 20) / 4),
+    )?;
+
+    for (index, offset) in (20..header.len()).step_by(4).enumerate() {
+        let width = u32_at(offset);
+
+        let description = if width == u32::MAX {
+            format!("column {} width: variable", index + 1)
+        } else {
+            format!("column {} width: {}", index + 1, width)
+        };
+
+        write_line(writer, offset, &header[offset..offset + 4], &description)?;
+    }
+
+    Ok(())
+}
+
+/// Write one annotated line of the dump. If there are more bytes than fit on a line, the rest
+/// go on the lines after it, without the description.
+///
+/// * `offset` - the offset of the first byte, from the start of the file
+/// * `bytes` - the bytes to show; a null column has none
+/// * `description` - what the bytes are
+fn write_line(
+    writer: &mut impl Write,
+    offset: usize,
+    bytes: &[u8],
+    description: &str,
+) -> anyhow::Result<()> {
+    let mut chunks = bytes.chunks(BYTES_PER_LINE);
+    let first = chunks.next().unwrap_or(&[]);
+
+    writeln!(
+        writer,
+        "{:>8}  {:<width$}  {}",
+        offset,
+        hex(first),
+        description,
+        width = HEX_WIDTH
+    )?;
+
+    for (i, chunk) in chunks.enumerate() {
+        writeln!(
+            writer,
+            "{:>8}  {}",
+            offset + (i + 1) * BYTES_PER_LINE,
+            hex(chunk)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The bytes as lowercase hex, separated by spaces, like `xxd` shows them.
+fn hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    hex.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::BufReader;
+
+    use crate::column_types::ColumnTypes;
+    use crate::explain::explain;
+
+    fn explain_to_string(file_name: &str, types: Option<&ColumnTypes>, rows: &[usize]) -> String {
+        let mut reader = BufReader::new(File::open(file_name).unwrap());
+        let mut output: Vec<u8> = vec![];
+
+        explain(&mut reader, types, rows, &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_explain_header() {
+        let output = explain_to_string("data/all-types.bin", None, &[]);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Header");
+        assert!(lines[1].starts_with("       0  4e 41 54 49 56 45 0a ff 0d 0a 00  "));
+        assert!(lines[1].ends_with("  signature (valid)"));
+        assert!(lines[2].ends_with("  header length: 61"));
+        assert!(lines[5].ends_with("  column count: 14"));
+        assert!(lines[9].starts_with("      32  ff ff ff ff  "));
+        assert!(lines[9].ends_with("  column 4 width: variable"));
+    }
+
+    #[test]
+    fn test_explain_rows() {
+        let types = ColumnTypes::from_reader(BufReader::new(
+            File::open("data/all-valid-types-with-names.txt").unwrap(),
+        ))
+        .unwrap();
+
+        let output = explain_to_string("data/all-types-with-nulls.bin", Some(&types), &[]);
+
+        assert!(output.contains("\nRow 1\n      76  6b 00 00 00  "));
+        assert!(output.contains("  null bits: 01000000 00000000\n"));
+        assert!(output.contains("  column 1 (IntCol): 8 bytes, Integer = 1\n"));
+        assert!(output.contains("  column 2 (FloatCol): null, Float\n"));
+        assert!(output.contains("\n     100  03 00 00 00  "));
+        assert!(output.contains("  column 4 (VarCharCol) length: 3\n"));
+        assert!(output.contains("\n     104  4f 4e 45  "));
+        assert!(output.contains("  column 4 (VarCharCol): 3 bytes, Varchar = ONE\n"));
+
+        // Values longer than a line carry on, without a description.
+        assert!(output.contains("\n     173  64 d6 12 00 00 00 00 00\n"));
+    }
+
+    #[test]
+    fn test_explain_selected_rows() {
+        let output = explain_to_string("data/all-types-ten-rows.bin", None, &[2, 4]);
+
+        assert!(!output.contains("Row 1\n"));
+        assert!(output.contains("\nRow 2\n     197  73 00 00 00  "));
+        assert!(!output.contains("Row 3\n"));
+        assert!(output.contains("\nRow 4\n"));
+        assert!(!output.contains("Row 5\n"));
+        assert!(output.contains("  column 1: 8 bytes\n"));
+    }
+
+    #[test]
+    fn test_explain_bad_row() {
+        // Make the `varchar` in row 5 longer than the row.
+        let mut bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+        bytes[592..596].copy_from_slice(&1000u32.to_le_bytes());
+
+        let mut output: Vec<u8> = vec![];
+        let result = explain(&mut &bytes[..], None, &[5], &mut output);
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("\nRow 5\n     560  73 00 00 00  "));
+        assert!(output.contains(
+            "\n     566  01 00 00 00 00 00 00 00 c3 f5 28 5c 8f c2 f1 bf  row data: 115 bytes, but "
+        ));
+        assert!(!output.contains("  column 1: 8 bytes\n"));
+        assert_eq!(result.unwrap_err().to_string(), "can't decode row 5");
+    }
+}
@@ -7,13 +7,12 @@ use anyhow::bail;
 
 use crate::read_u8;
 
-const FILE_SIGNATURE_LENGTH: usize = 11;
-const VALID_FILE_SIGNATURE_BYTES: [u8; 11] = [
+pub const FILE_SIGNATURE_LENGTH: usize = 11;
+pub const VALID_FILE_SIGNATURE_BYTES: [u8; 11] = [
     0x4e, 0x41, 0x54, 0x49, 0x56, 0x45, 0x0a, 0xff, 0x0d, 0x0a, 0x00,
 ];
 
 #[derive(Debug)]
-#[allow(unused)]
 /// A static signature at the beginning over every Vertica native file. The byte layout
 /// of this signature can be found [here](https://www.vertica.com/docs/9.3.x/HTML/Content/Authoring/AdministratorsGuide/BinaryFilesAppendix/FileSignature.htm).
 ///
@@ -41,17 +40,29 @@ impl error::Error for FileSignatureError {
 
 impl FileSignature {
     pub fn from_reader(reader: &mut impl Read) -> anyhow::Result<Self> {
+        let signature = Self::from_reader_unchecked(reader)?;
+
+        validate(&signature.data)?;
+
+        Ok(signature)
+    }
+
+    /// Read the signature without validating it. This is for reporting on files that might be
+    /// broken, where an invalid signature is something to tell the user about, not an error.
+    pub fn from_reader_unchecked(reader: &mut impl Read) -> anyhow::Result<Self> {
         let mut data: [u8; 11] = [0; 11];
 
-        for i in 0..FILE_SIGNATURE_LENGTH {
-            let byte = read_u8(reader)?;
-            data[i] = byte;
+        for byte in data.iter_mut().take(FILE_SIGNATURE_LENGTH) {
+            *byte = read_u8(reader)?;
         }
 
-        validate(&data)?;
-
         Ok(FileSignature { data })
     }
+
+    /// Whether the signature matches the one every native file starts with.
+    pub fn is_valid(&self) -> bool {
+        validate(&self.data).is_ok()
+    }
 }
 
 fn validate(data: &[u8; 11]) -> anyhow::Result<()> {
@@ -88,4 +99,13 @@ mod tests {
 
         assert!(res.is_err())
     }
+
+    #[test]
+    fn test_read_unchecked_from_bad_file() {
+        let mut file = BufReader::new(File::open("data/all-types-with-bad-signature.bin").unwrap());
+
+        let signature = FileSignature::from_reader_unchecked(&mut file).unwrap();
+
+        assert!(!signature.is_valid())
+    }
 }
@@ -0,0 +1,944 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use anyhow::{bail, Context};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::{Regex, RegexBuilder};
+
+use crate::column_type::{ColumnType, MICROSECONDS_PER_DAY};
+use crate::column_types::ColumnTypes;
+use crate::column_value::ColumnValue;
+use crate::vertica_native_file::Row;
+use crate::Args;
+
+/// A parsed `--where` expression, used to drop rows before they're written out. The language is
+/// a small subset of SQL:
+///
+/// * comparisons: `=`, `!=`, `<>`, `<`, `<=`, `>`, `>=`
+/// * `AND`, `OR`, `NOT`, and parentheses for grouping
+/// * `IS NULL` and `IS NOT NULL`
+/// * `LIKE` and `ILIKE`, with `%` and `_` wildcards, and `~` and `!~` for regular expressions
+///
+/// Columns are referred to by name, by name in double quotes (for names that aren't simple
+/// words), or by position, counting from 1, as `$1`, `$2`, etc. Literals are numbers, strings in
+/// single quotes, `TRUE`, `FALSE`, and `NULL`. Dates and times can be given as
+/// `DATE '2020-01-31'`, `TIMESTAMP '2020-01-31 12:34:56'`, or `TIME '12:34:56'`, or just as
+/// strings, when they're compared against a column of that type.
+///
+/// Comparisons follow SQL's rules for nulls, so a comparison against a null is neither true nor
+/// false, and only rows where the whole expression is true are kept.
+///
+/// Examples:
+///   IntCol > 5 AND VarCharCol LIKE 'A%'
+///   The_Date >= '1999-01-01' AND NOT (FloatCol IS NULL OR FloatCol < 0)
+///   $3 ~ '^[0-9]+$'
+///
+#[derive(Debug)]
+pub struct Filter {
+    expression: Expression,
+}
+
+#[derive(Debug)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, Comparison, Operand),
+    IsNull(Operand, bool),
+    /// `LIKE`, `ILIKE`, `~`, and their negations. The bool is true when negated.
+    Matches(Operand, Regex, bool),
+}
+
+#[derive(Debug)]
+enum Operand {
+    Column(usize),
+    Literal(Scalar<'static>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// The kinds of values that can be compared to each other. Column types that don't have a
+/// natural ordering of their own (binary, intervals, etc.) are compared as their formatted text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Number,
+    Text,
+    Boolean,
+    Date,
+    Timestamp,
+    Time,
+    Null,
+}
+
+/// A value that's being compared, from either a column or a literal.
+#[derive(Debug, Clone, PartialEq)]
+enum Scalar<'a> {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Text(Cow<'a, str>),
+    Boolean(bool),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Time(NaiveTime),
+    /// A `date` or `timestamp` of `infinity`, after every other one
+    Infinity,
+    /// A `date` or `timestamp` of `-infinity`, before every other one
+    NegativeInfinity,
+    /// A `date` or `timestamp` too far away for `NaiveDate`, as its day, counting from 2000-01-01
+    OutOfRange(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    QuotedWord(String),
+    Position(usize),
+    String(String),
+    Number(String),
+    Operator(&'static str),
+    LeftParen,
+    RightParen,
+}
+
+impl Filter {
+    /// Parse the `--where` expression, checking it against the column types.
+    ///
+    /// * `text` - the expression
+    /// * `types` - the column types, used to look up column names, and check comparisons
+    pub fn parse(text: &str, types: &ColumnTypes) -> anyhow::Result<Filter> {
+        let tokens = tokenize(text)?;
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            types,
+        };
+
+        let expression = parser.parse_or()?;
+
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {:?} in filter", token);
+        }
+
+        Ok(Filter { expression })
+    }
+
+    /// Does the `row` satisfy the filter?
+    ///
+    /// * `row` - the row to check
+    /// * `types` - the ColumnTypes struct with conversion info
+    /// * `args` - all the command line arguments
+    pub fn matches(&self, row: &Row, types: &ColumnTypes, args: &Args) -> bool {
+        self.expression.evaluate(row, types, args) == Some(true)
+    }
+}
+
+impl Expression {
+    /// Evaluate the expression using SQL's three-valued logic, where `None` means unknown.
+    fn evaluate(&self, row: &Row, types: &ColumnTypes, args: &Args) -> Option<bool> {
+        match self {
+            Expression::And(left, right) => {
+                match (
+                    left.evaluate(row, types, args),
+                    right.evaluate(row, types, args),
+                ) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Expression::Or(left, right) => {
+                match (
+                    left.evaluate(row, types, args),
+                    right.evaluate(row, types, args),
+                ) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Expression::Not(expression) => expression.evaluate(row, types, args).map(|b| !b),
+            Expression::Compare(left, comparison, right) => {
+                let left = left.value(row, types, args);
+                let right = right.value(row, types, args);
+
+                compare(&left, &right).map(|ordering| match comparison {
+                    Comparison::Equal => ordering == Ordering::Equal,
+                    Comparison::NotEqual => ordering != Ordering::Equal,
+                    Comparison::Less => ordering == Ordering::Less,
+                    Comparison::LessOrEqual => ordering != Ordering::Greater,
+                    Comparison::Greater => ordering == Ordering::Greater,
+                    Comparison::GreaterOrEqual => ordering != Ordering::Less,
+                })
+            }
+            Expression::IsNull(operand, negated) => {
+                let is_null = operand.value(row, types, args) == Scalar::Null;
+                Some(is_null != *negated)
+            }
+            Expression::Matches(operand, regex, negated) => operand
+                .text(row, types, args)
+                .map(|text| regex.is_match(&text) != *negated),
+        }
+    }
+}
+
+impl Operand {
+    /// The value of the operand, for the current row.
+    fn value<'a>(&'a self, row: &Row<'a>, types: &ColumnTypes, args: &Args) -> Scalar<'a> {
+        let index = match self {
+            Operand::Literal(Scalar::Text(text)) => return Scalar::Text(Cow::Borrowed(text)),
+            Operand::Literal(scalar) => return scalar.clone(),
+            Operand::Column(index) => *index,
+        };
+
+        let column_type = &types.column_types[index];
+
+        match column_type.decode(row.column(index)) {
+            ColumnValue::Null => Scalar::Null,
+            ColumnValue::Integer(value) => Scalar::Integer(value),
+            ColumnValue::Float(value) => Scalar::Float(value),
+            ColumnValue::String(value) => Scalar::Text(Cow::Borrowed(value)),
+            ColumnValue::Boolean(value) => Scalar::Boolean(value),
+            ColumnValue::Date(value) => Scalar::Date(value),
+            ColumnValue::Timestamp(value) | ColumnValue::TimestampTz(value) => {
+                Scalar::Timestamp(value)
+            }
+            ColumnValue::Time(value) => Scalar::Time(value),
+            ColumnValue::Infinity => Scalar::Infinity,
+            ColumnValue::NegativeInfinity => Scalar::NegativeInfinity,
+            // A `date` is out of range by a number of days, but a `timestamp` by microseconds.
+            ColumnValue::OutOfRange(value) => match column_type {
+                ColumnType::Date => Scalar::OutOfRange(value),
+                _ => Scalar::OutOfRange(value.div_euclid(MICROSECONDS_PER_DAY)),
+            },
+            ColumnValue::Numeric(_) => {
+                let text = self.format(row, index, types, args);
+                parse_number(&text).unwrap_or(Scalar::Null)
+            }
+            ColumnValue::TimeTz(..)
+            | ColumnValue::Binary(_)
+            | ColumnValue::Interval(_)
+            | ColumnValue::IntervalYearToMonth(_)
+            | ColumnValue::Uuid(_) => {
+                Scalar::Text(Cow::Owned(self.format(row, index, types, args)))
+            }
+        }
+    }
+
+    /// The value of the operand as text, for matching against patterns. Anything that isn't
+    /// already text is formatted just as it would be for output.
+    fn text<'a>(&'a self, row: &Row<'a>, types: &ColumnTypes, args: &Args) -> Option<Cow<'a, str>> {
+        match self.value(row, types, args) {
+            Scalar::Null => None,
+            Scalar::Text(text) => Some(text),
+            Scalar::Integer(value) => Some(Cow::Owned(value.to_string())),
+            Scalar::Float(value) => Some(Cow::Owned(value.to_string())),
+            _ => match self {
+                Operand::Column(index) => Some(Cow::Owned(self.format(row, *index, types, args))),
+                Operand::Literal(_) => None,
+            },
+        }
+    }
+
+    fn format(&self, row: &Row, index: usize, types: &ColumnTypes, args: &Args) -> String {
+        types.column_types[index].format_value(
+            row.column(index),
+            &types.column_conversions[index],
+            types.precision(index),
+            args,
+        )
+    }
+
+    fn kind(&self, types: &ColumnTypes) -> Kind {
+        match self {
+            Operand::Column(index) => match types.column_types[*index] {
+                ColumnType::Integer | ColumnType::Float | ColumnType::Numeric => Kind::Number,
+                ColumnType::Char
+                | ColumnType::Varchar
+                | ColumnType::TimeTz
+                | ColumnType::Varbinary
+                | ColumnType::Binary
+                | ColumnType::Interval
+                | ColumnType::IntervalYearToMonth
+                | ColumnType::Uuid => Kind::Text,
+                ColumnType::Boolean => Kind::Boolean,
+                ColumnType::Date => Kind::Date,
+                ColumnType::Timestamp | ColumnType::TimestampTz => Kind::Timestamp,
+                ColumnType::Time => Kind::Time,
+            },
+            Operand::Literal(scalar) => match scalar {
+                Scalar::Null => Kind::Null,
+                Scalar::Integer(_) | Scalar::Float(_) => Kind::Number,
+                Scalar::Text(_) => Kind::Text,
+                Scalar::Boolean(_) => Kind::Boolean,
+                Scalar::Date(_) => Kind::Date,
+                Scalar::Timestamp(_) => Kind::Timestamp,
+                Scalar::Time(_) => Kind::Time,
+                // These only ever come from columns.
+                Scalar::Infinity | Scalar::NegativeInfinity | Scalar::OutOfRange(_) => {
+                    Kind::Timestamp
+                }
+            },
+        }
+    }
+}
+
+/// Compare two values, or return `None` if either of them is null.
+fn compare(left: &Scalar, right: &Scalar) -> Option<Ordering> {
+    match (left, right) {
+        (Scalar::Integer(l), Scalar::Integer(r)) => Some(l.cmp(r)),
+        (Scalar::Integer(l), Scalar::Float(r)) => (*l as f64).partial_cmp(r),
+        (Scalar::Float(l), Scalar::Integer(r)) => l.partial_cmp(&(*r as f64)),
+        (Scalar::Float(l), Scalar::Float(r)) => l.partial_cmp(r),
+        (Scalar::Text(l), Scalar::Text(r)) => Some(l.cmp(r)),
+        (Scalar::Boolean(l), Scalar::Boolean(r)) => Some(l.cmp(r)),
+        (Scalar::Infinity | Scalar::NegativeInfinity | Scalar::OutOfRange(_), _)
+        | (_, Scalar::Infinity | Scalar::NegativeInfinity | Scalar::OutOfRange(_)) => {
+            Some(day(left)?.cmp(&day(right)?))
+        }
+        (Scalar::Date(l), Scalar::Date(r)) => Some(l.cmp(r)),
+        (Scalar::Date(l), Scalar::Timestamp(r)) => Some(l.and_hms(0, 0, 0).cmp(r)),
+        (Scalar::Timestamp(l), Scalar::Date(r)) => Some(l.cmp(&r.and_hms(0, 0, 0))),
+        (Scalar::Timestamp(l), Scalar::Timestamp(r)) => Some(l.cmp(r)),
+        (Scalar::Time(l), Scalar::Time(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+/// The day of a date or timestamp, counting from 2000-01-01, like Vertica does, with `infinity`
+/// after every other day, and `-infinity` before, or `None` if it isn't a date or timestamp.
+fn day(scalar: &Scalar) -> Option<i64> {
+    let epoch = NaiveDate::from_ymd(2000, 1, 1);
+
+    match scalar {
+        Scalar::Infinity => Some(i64::MAX),
+        Scalar::NegativeInfinity => Some(i64::MIN),
+        Scalar::OutOfRange(day) => Some(*day),
+        Scalar::Date(date) => Some((*date - epoch).num_days()),
+        Scalar::Timestamp(timestamp) => Some((timestamp.date() - epoch).num_days()),
+        _ => None,
+    }
+}
+
+fn parse_number(text: &str) -> Option<Scalar<'static>> {
+    if let Ok(value) = text.parse::<i64>() {
+        Some(Scalar::Integer(value))
+    } else {
+        text.parse::<f64>().ok().map(Scalar::Float)
+    }
+}
+
+fn parse_date(text: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").with_context(|| format!("invalid date: {}", text))
+}
+
+fn parse_timestamp(text: &str) -> anyhow::Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| parse_date(text).map(|date| date.and_hms(0, 0, 0)))
+        .with_context(|| format!("invalid timestamp: {}", text))
+}
+
+fn parse_time(text: &str) -> anyhow::Result<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M:%S%.f")
+        .with_context(|| format!("invalid time: {}", text))
+}
+
+/// Turn a `LIKE` pattern into an anchored regular expression. `%` matches any run of
+/// characters, `_` matches any single character, and `\` escapes the next character.
+fn like_to_regex(pattern: &str, case_insensitive: bool) -> anyhow::Result<Regex> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push('$');
+
+    Ok(RegexBuilder::new(&regex)
+        .dot_matches_new_line(true)
+        .case_insensitive(case_insensitive)
+        .build()?)
+}
+
+/// Break the expression up into tokens.
+fn tokenize(text: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        // A `-` is part of a number if it can't be a binary operator, which we don't have.
+        let starts_number = c.is_ascii_digit()
+            || (c == '-' || c == '.')
+                && matches!(chars.clone().nth(1), Some(n) if n.is_ascii_digit());
+
+        if c.is_whitespace() {
+            chars.next();
+        } else if starts_number {
+            let mut number = String::new();
+            number.push(c);
+            chars.next();
+
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' {
+                    number.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    word.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            tokens.push(Token::Word(word));
+        } else if c == '\'' || c == '"' {
+            // Strings are in single quotes, names in double quotes. Either can contain their own
+            // quote character by doubling it.
+            let quote = c;
+            let mut string = String::new();
+            chars.next();
+
+            loop {
+                match chars.next() {
+                    Some(c) if c == quote => {
+                        if chars.peek() == Some(&quote) {
+                            string.push(quote);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    Some(c) => string.push(c),
+                    None => bail!("unterminated {} in filter", quote),
+                }
+            }
+
+            tokens.push(if quote == '\'' {
+                Token::String(string)
+            } else {
+                Token::QuotedWord(string)
+            });
+        } else if c == '$' {
+            chars.next();
+
+            let mut digits = String::new();
+
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            match digits.parse::<usize>() {
+                Ok(position) => tokens.push(Token::Position(position)),
+                Err(_) => bail!("expected a column number after $ in filter"),
+            }
+        } else {
+            chars.next();
+
+            let token = match (c, chars.peek()) {
+                ('(', _) => Token::LeftParen,
+                (')', _) => Token::RightParen,
+                ('=', _) => Token::Operator("="),
+                ('!', Some('=')) => Token::Operator("!="),
+                ('!', Some('~')) => Token::Operator("!~"),
+                ('<', Some('>')) => Token::Operator("!="),
+                ('<', Some('=')) => Token::Operator("<="),
+                ('>', Some('=')) => Token::Operator(">="),
+                ('<', _) => Token::Operator("<"),
+                ('>', _) => Token::Operator(">"),
+                ('~', _) => Token::Operator("~"),
+                _ => bail!("unexpected character in filter: {}", c),
+            };
+
+            // The two-character operators need their second character consumed, too.
+            if matches!(token, Token::Operator(op) if op.len() == 2) {
+                chars.next();
+            }
+
+            tokens.push(token);
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// A recursive-descent parser over the tokens, from lowest precedence (`OR`) to highest.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    types: &'a ColumnTypes,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// If the next token is the keyword `keyword`, consume it, and return true.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.parse_and()?;
+
+        while self.keyword("or") {
+            left = Expression::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.parse_not()?;
+
+        while self.keyword("and") {
+            left = Expression::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> anyhow::Result<Expression> {
+        if self.keyword("not") {
+            Ok(Expression::Not(Box::new(self.parse_not()?)))
+        } else {
+            self.parse_predicate()
+        }
+    }
+
+    fn parse_predicate(&mut self) -> anyhow::Result<Expression> {
+        if self.peek() == Some(&Token::LeftParen) {
+            self.next();
+
+            let expression = self.parse_or()?;
+
+            match self.next() {
+                Some(Token::RightParen) => return Ok(expression),
+                _ => bail!("missing ) in filter"),
+            }
+        }
+
+        let left = self.parse_operand()?;
+
+        if self.keyword("is") {
+            let negated = self.keyword("not");
+
+            if !self.keyword("null") {
+                bail!("expected NULL after IS in filter");
+            }
+
+            return Ok(Expression::IsNull(left, negated));
+        }
+
+        let negated = self.keyword("not");
+
+        if self.keyword("like") {
+            let pattern = self.parse_pattern("LIKE")?;
+            return Ok(Expression::Matches(
+                left,
+                like_to_regex(&pattern, false)?,
+                negated,
+            ));
+        }
+
+        if self.keyword("ilike") {
+            let pattern = self.parse_pattern("ILIKE")?;
+            return Ok(Expression::Matches(
+                left,
+                like_to_regex(&pattern, true)?,
+                negated,
+            ));
+        }
+
+        if negated {
+            bail!("expected LIKE or ILIKE after NOT in filter");
+        }
+
+        let operator = match self.peek() {
+            Some(Token::Operator(operator)) => *operator,
+            // A boolean column can stand on its own, as a shorthand for `column = TRUE`.
+            _ if left.kind(self.types) == Kind::Boolean => {
+                return Ok(Expression::Compare(
+                    left,
+                    Comparison::Equal,
+                    Operand::Literal(Scalar::Boolean(true)),
+                ));
+            }
+            Some(token) => bail!("expected a comparison in filter, found {:?}", token),
+            None => bail!("expected a comparison at end of filter"),
+        };
+
+        self.next();
+
+        let comparison = match operator {
+            "~" | "!~" => {
+                let pattern = self.parse_pattern(operator)?;
+                let regex = Regex::new(&pattern)
+                    .with_context(|| format!("invalid regular expression: {}", pattern))?;
+                return Ok(Expression::Matches(left, regex, operator == "!~"));
+            }
+            "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            _ => Comparison::GreaterOrEqual,
+        };
+
+        let right = self.parse_operand()?;
+
+        let (left, right) = self.coerce(left, right)?;
+
+        Ok(Expression::Compare(left, comparison, right))
+    }
+
+    fn parse_pattern(&mut self, operator: &str) -> anyhow::Result<String> {
+        match self.next() {
+            Some(Token::String(pattern)) => Ok(pattern),
+            _ => bail!("expected a quoted pattern after {} in filter", operator),
+        }
+    }
+
+    fn parse_operand(&mut self) -> anyhow::Result<Operand> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => bail!("unexpected end of filter"),
+        };
+
+        let operand = match token {
+            Token::Number(number) => match parse_number(&number) {
+                Some(scalar) => Operand::Literal(scalar),
+                None => bail!("invalid number in filter: {}", number),
+            },
+            Token::String(string) => Operand::Literal(Scalar::Text(Cow::Owned(string))),
+            Token::Position(position) => {
+                if position < 1 || position > self.types.column_types.len() {
+                    bail!("column number out of range in filter: ${}", position);
+                }
+
+                Operand::Column(position - 1)
+            }
+            Token::QuotedWord(name) => Operand::Column(self.column(&name)?),
+            Token::Word(word) => {
+                // A date or time literal is a keyword followed by a string.
+                let string = match self.peek() {
+                    Some(Token::String(string)) => Some(string.clone()),
+                    _ => None,
+                };
+
+                match (word.to_lowercase().as_str(), string) {
+                    ("true", _) => Operand::Literal(Scalar::Boolean(true)),
+                    ("false", _) => Operand::Literal(Scalar::Boolean(false)),
+                    ("null", _) => Operand::Literal(Scalar::Null),
+                    ("date", Some(string)) => {
+                        self.next();
+                        Operand::Literal(Scalar::Date(parse_date(&string)?))
+                    }
+                    ("timestamp", Some(string)) => {
+                        self.next();
+                        Operand::Literal(Scalar::Timestamp(parse_timestamp(&string)?))
+                    }
+                    ("time", Some(string)) => {
+                        self.next();
+                        Operand::Literal(Scalar::Time(parse_time(&string)?))
+                    }
+                    _ => Operand::Column(self.column(&word)?),
+                }
+            }
+            token => bail!("unexpected {:?} in filter", token),
+        };
+
+        Ok(operand)
+    }
+
+    /// Find a column by name. An exact match wins, but otherwise case doesn't matter.
+    fn column(&self, name: &str) -> anyhow::Result<usize> {
+        let names = &self.types.column_names;
+
+        match names.iter().position(|n| n == name) {
+            Some(index) => Ok(index),
+            None => match names.iter().position(|n| n.eq_ignore_ascii_case(name)) {
+                Some(index) => Ok(index),
+                None => bail!("no such column in filter: {}", name),
+            },
+        }
+    }
+
+    /// Make sure the two sides of a comparison can be compared. String literals compared
+    /// against dates and times are parsed here, once, rather than for every row.
+    fn coerce(&self, left: Operand, right: Operand) -> anyhow::Result<(Operand, Operand)> {
+        let left_kind = left.kind(self.types);
+        let right_kind = right.kind(self.types);
+
+        let compatible = |a: Kind, b: Kind| {
+            a == b
+                || a == Kind::Null
+                || b == Kind::Null
+                || matches!(
+                    (a, b),
+                    (Kind::Date, Kind::Timestamp) | (Kind::Timestamp, Kind::Date)
+                )
+        };
+
+        if compatible(left_kind, right_kind) {
+            return Ok((left, right));
+        }
+
+        let coerced = |kind: Kind, operand: Operand| -> anyhow::Result<Operand> {
+            let text = match &operand {
+                Operand::Literal(Scalar::Text(text)) => text,
+                _ => return Ok(operand),
+            };
+
+            let scalar = match kind {
+                Kind::Date => Scalar::Date(parse_date(text)?),
+                Kind::Timestamp => Scalar::Timestamp(parse_timestamp(text)?),
+                Kind::Time => Scalar::Time(parse_time(text)?),
+                _ => return Ok(operand),
+            };
+
+            Ok(Operand::Literal(scalar))
+        };
+
+        let left = coerced(right_kind, left)?;
+        let right = coerced(left_kind, right)?;
+
+        let left_kind = left.kind(self.types);
+        let right_kind = right.kind(self.types);
+
+        if !compatible(left_kind, right_kind) {
+            bail!(
+                "can't compare {:?} with {:?} in filter",
+                left_kind,
+                right_kind
+            );
+        }
+
+        Ok((left, right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::assemble::assemble;
+    use crate::column_type::ColumnType;
+    use crate::column_types::ColumnTypes;
+    use crate::filter::Filter;
+    use crate::vertica_native_file::VerticaNativeFile;
+    use crate::Args;
+
+    fn types() -> ColumnTypes {
+        let file = BufReader::new(File::open("data/all-valid-types-with-names.txt").unwrap());
+        ColumnTypes::from_reader(file).unwrap()
+    }
+
+    /// Check each filter against the single row of `file`
+    fn check(file: &str, filters: &[(&str, bool)]) {
+        let types = types();
+        let args = Args::with_defaults();
+
+        let mut reader = BufReader::new(File::open(file).unwrap());
+        let mut native_file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+        let row = native_file.next_row().unwrap();
+
+        for (text, expected) in filters {
+            let filter = Filter::parse(text, &types).unwrap();
+            assert_eq!(*expected, filter.matches(&row, &types, &args), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_comparisons() {
+        check(
+            "data/all-types.bin",
+            &[
+                ("IntCol = 1", true),
+                ("IntCol != 1", false),
+                ("IntCol <> 1", false),
+                ("IntCol >= 1", true),
+                ("IntCol < 1", false),
+                ("1 = IntCol", true),
+                ("FloatCol < -1.1", true),
+                ("FloatCol > -1.2", true),
+                ("$4 = 'ONE'", true),
+                ("\"CharCol\" = 'one'", true),
+                ("intcol = 1", true),
+                ("Bools", true),
+                ("Bools = FALSE", false),
+                ("Num_Num_Num = 1234532", true),
+                ("VB3 = 'ABCD'", true),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_dates_and_times() {
+        check(
+            "data/all-types.bin",
+            &[
+                ("The_Date = '1999-01-08'", true),
+                ("The_Date = DATE '1999-01-08'", true),
+                ("The_Date > DATE '1999-01-08'", false),
+                ("The_Date < TIMESTAMP '1999-01-08 00:00:01'", true),
+                ("TS_Elliot > '1999-02-23'", true),
+                ("TS_Elliot < TIMESTAMP '1999-02-23 03:11:52.351'", true),
+                ("TS_TZ = '1999-01-08 12:04:37'", true),
+                ("Clock = TIME '07:09:23'", true),
+                ("Clock > '07:00:00'", true),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_infinite_and_out_of_range_dates() {
+        let types = ColumnTypes {
+            column_types: vec![ColumnType::Date, ColumnType::Timestamp],
+            column_names: vec!["Day".to_string(), "Stamp".to_string()],
+            column_conversions: vec![None, None],
+            column_parameters: vec![vec![], vec![]],
+        };
+        let args = Args::with_defaults();
+        let bytes = assemble(
+            "@signature\n@header 8 8\n\
+             @row\n@nulls\ndate:infinity timestamp:-infinity\n\
+             @row\n@nulls\nint64:-200000000 int64:9000000000000000000\n",
+        )
+        .unwrap();
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+
+        let filters = [
+            ("Day > DATE '9999-12-31'", true, false),
+            ("Day < TIMESTAMP '1999-01-08 12:34:56'", false, true),
+            ("Day = '1999-01-08'", false, false),
+            ("Stamp < DATE '0001-01-01'", true, false),
+            ("Stamp > TIMESTAMP '1999-01-08 12:34:56'", false, true),
+            ("Stamp > Day", false, true),
+            ("Day > Stamp", true, false),
+        ];
+
+        // Each filter has what it's expected to give for the first row, then for the second.
+        for second in &[false, true] {
+            let row = native_file.next_row().unwrap();
+
+            for (text, first_expected, second_expected) in &filters {
+                let expected = if *second {
+                    second_expected
+                } else {
+                    first_expected
+                };
+                let filter = Filter::parse(text, &types).unwrap();
+                assert_eq!(*expected, filter.matches(&row, &types, &args), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_logic_and_nulls() {
+        check(
+            "data/all-types-with-nulls.bin",
+            &[
+                ("FloatCol IS NULL", true),
+                ("FloatCol IS NOT NULL", false),
+                ("IntCol IS NULL", false),
+                ("FloatCol = 1", false),
+                ("NOT FloatCol = 1", false),
+                ("FloatCol = 1 OR IntCol = 1", true),
+                ("FloatCol = 1 AND IntCol = 1", false),
+                ("NOT (FloatCol = 1 AND IntCol = 2)", true),
+                (
+                    "IntCol = 2 OR (CharCol = 'one' AND VarCharCol = 'ONE')",
+                    true,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_patterns() {
+        check(
+            "data/all-types.bin",
+            &[
+                ("VarCharCol LIKE 'O%'", true),
+                ("VarCharCol LIKE 'o%'", false),
+                ("VarCharCol ILIKE 'o%'", true),
+                ("VarCharCol NOT LIKE 'O_E'", false),
+                ("VarCharCol LIKE 'O_'", false),
+                ("CharCol ~ '^o.e$'", true),
+                ("CharCol !~ 'n'", false),
+                ("The_Date LIKE '1999-%'", true),
+                ("IntCol ~ '^1$'", true),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_bad_filters() {
+        let types = types();
+
+        for text in [
+            "NoSuchColumn = 1",
+            "$15 = 1",
+            "IntCol =",
+            "IntCol = 'one'",
+            "The_Date = 'not a date'",
+            "(IntCol = 1",
+            "IntCol = 1 extra",
+            "CharCol LIKE 1",
+            "CharCol ~ '('",
+            "'unterminated",
+            "IntCol # 1",
+        ] {
+            assert!(Filter::parse(text, &types).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+
+use anyhow::{bail, Context};
+use chrono::{NaiveDate, NaiveTime};
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use uuid::Builder;
+
+use crate::args::GenerateArgs;
+use crate::assemble::{encode_date, encode_time_tz, encode_timestamp};
+use crate::column_conversion::ColumnConversion;
+use crate::column_type::ColumnType;
+use crate::column_types::ColumnTypes;
+use crate::file_signature::VALID_FILE_SIGNATURE_BYTES;
+use crate::read_types_file;
+use crate::vertica_native_file::bitfield_length;
+
+/// The length Vertica gives `char` and `binary` columns that don't have one
+const DEFAULT_FIXED_LENGTH: u32 = 1;
+
+/// The length Vertica gives `varchar` and `varbinary` columns that don't have one
+const DEFAULT_VARIABLE_LENGTH: u32 = 80;
+
+/// The precision Vertica gives `numeric` columns that don't have one
+const DEFAULT_PRECISION: u32 = 37;
+
+/// The most digits a generated `numeric` has, so that its value fits in the last word
+const MAX_NUMERIC_DIGITS: u32 = 19;
+
+/// The number of microseconds in a day
+const DAY_MICROS: i64 = 24 * 3600 * 1_000_000;
+
+/// Run the `generate` subcommand, writing the file to `args.output`.
+///
+/// * `args` - the subcommand's arguments
+pub fn generate_file(args: &GenerateArgs) -> anyhow::Result<()> {
+    if !(0.0..=1.0).contains(&args.null_probability) {
+        bail!(
+            "null probability must be between 0 and 1, not {}",
+            args.null_probability
+        );
+    }
+
+    let types = read_types_file(&args.types)?;
+
+    if args.output == "-" {
+        let stdout = stdout();
+        let mut writer = BufWriter::new(stdout.lock());
+
+        generate(
+            &types,
+            args.rows,
+            args.null_probability,
+            args.seed,
+            &mut writer,
+        )?;
+        writer.flush()?;
+    } else {
+        let file = File::create(&args.output)
+            .with_context(|| format!("creating output file [{}]", args.output))?;
+        let mut writer = BufWriter::new(file);
+
+        generate(
+            &types,
+            args.rows,
+            args.null_probability,
+            args.seed,
+            &mut writer,
+        )
+        .and_then(|_| Ok(writer.flush()?))
+        .with_context(|| format!("writing output file [{}]", args.output))?;
+    }
+
+    Ok(())
+}
+
+/// Write a native file of random values, one column for each entry in the types file. The same
+/// types, row count, null probability and seed always give the same file.
+///
+/// * `types` - the column types; their lengths and precisions are honored, and so are the
+///   `ipaddress` and `macaddress` conversions, which get valid addresses
+/// * `rows` - the number of rows to write
+/// * `null_probability` - the chance, from 0 to 1, of each value being null
+/// * `seed` - the seed for the random number generator
+/// * `writer` - where to write the file
+pub fn generate(
+    types: &ColumnTypes,
+    rows: usize,
+    null_probability: f64,
+    seed: u64,
+    writer: &mut impl Write,
+) -> anyhow::Result<()> {
+    let columns = (0..types.column_types.len())
+        .map(|index| Column::new(types, index))
+        .collect::<anyhow::Result<Vec<Column>>>()?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    writer.write_all(&VALID_FILE_SIGNATURE_BYTES)?;
+    writer.write_all(&(5 + 4 * columns.len() as u32).to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&[0])?;
+    writer.write_all(&(columns.len() as u16).to_le_bytes())?;
+
+    for column in &columns {
+        writer.write_all(&column.width().to_le_bytes())?;
+    }
+
+    let mut bitfield = vec![0; bitfield_length(columns.len())];
+    let mut data: Vec<u8> = vec![];
+
+    for _ in 0..rows {
+        bitfield.fill(0);
+        data.clear();
+
+        for (index, column) in columns.iter().enumerate() {
+            if rng.gen_bool(null_probability) {
+                bitfield[index / 8] |= 0x80 >> (index % 8);
+            } else {
+                column.generate(&mut rng, &mut data);
+            }
+        }
+
+        writer.write_all(&(data.len() as u32).to_le_bytes())?;
+        writer.write_all(&bitfield)?;
+        writer.write_all(&data)?;
+    }
+
+    Ok(())
+}
+
+/// What's needed to generate the values of one column.
+struct Column<'a> {
+    column_type: &'a ColumnType,
+    conversion: &'a Option<ColumnConversion>,
+    /// The length of a string or binary column, or the precision of a `numeric` column
+    length: u32,
+}
+
+impl<'a> Column<'a> {
+    /// Work out how to generate the column at `index`, filling in Vertica's defaults for any
+    /// length or precision that the types file leaves out.
+    fn new(types: &'a ColumnTypes, index: usize) -> anyhow::Result<Self> {
+        let column_type = &types.column_types[index];
+        let conversion = &types.column_conversions[index];
+        let parameters = &types.column_parameters[index];
+
+        let length = match column_type {
+            ColumnType::Char | ColumnType::Binary => DEFAULT_FIXED_LENGTH,
+            ColumnType::Varchar | ColumnType::Varbinary => DEFAULT_VARIABLE_LENGTH,
+            ColumnType::Numeric => DEFAULT_PRECISION,
+            _ => 0,
+        };
+        let length = parameters.first().copied().unwrap_or(length);
+
+        let column = Column {
+            column_type,
+            conversion,
+            length,
+        };
+
+        match (column_type, conversion) {
+            (ColumnType::Char | ColumnType::Binary, _) if length == 0 => {
+                bail!(
+                    "column {}: {:?} needs a length of at least 1",
+                    index + 1,
+                    column_type
+                )
+            }
+            (ColumnType::Numeric, _) if length == 0 => {
+                bail!(
+                    "column {}: Numeric needs a precision of at least 1",
+                    index + 1
+                )
+            }
+            (ColumnType::Binary, Some(ColumnConversion::IpAddress))
+                if length != 6 && length != 16 =>
+            {
+                bail!(
+                    "column {}: ipaddress needs a Binary length of 6 or 16, not {}",
+                    index + 1,
+                    length
+                )
+            }
+            (ColumnType::Varbinary, Some(ColumnConversion::IpAddress)) if length < 6 => {
+                bail!(
+                    "column {}: ipaddress needs a Varbinary length of at least 6, not {}",
+                    index + 1,
+                    length
+                )
+            }
+            (ColumnType::Varbinary, Some(ColumnConversion::MacAddress)) if length < 6 => {
+                bail!(
+                    "column {}: macaddress needs a Varbinary length of at least 6, not {}",
+                    index + 1,
+                    length
+                )
+            }
+            _ => {}
+        }
+
+        Ok(column)
+    }
+
+    /// The column's width, as it goes in the header
+    fn width(&self) -> u32 {
+        match self.column_type {
+            ColumnType::Boolean => 1,
+            ColumnType::Char | ColumnType::Binary => self.length,
+            ColumnType::Varchar | ColumnType::Varbinary => u32::MAX,
+            ColumnType::Numeric => 8 + self.length.div_ceil(19) * 8,
+            ColumnType::Uuid => 16,
+            _ => 8,
+        }
+    }
+
+    /// Append a random value for the column to `data`, with its length in front of it if the
+    /// column is variable-width.
+    fn generate(&self, rng: &mut StdRng, data: &mut Vec<u8>) {
+        let value = match self.column_type {
+            ColumnType::Integer => rng.gen::<i64>().to_le_bytes().to_vec(),
+            ColumnType::Float => rng.gen_range(-1e6..1e6_f64).to_le_bytes().to_vec(),
+            ColumnType::Char => {
+                let length = rng.gen_range(1..=self.length as usize);
+                let mut value = random_string(rng, length);
+                value.resize(self.length as usize, b' ');
+                value
+            }
+            ColumnType::Varchar => {
+                let length = rng.gen_range(0..=self.length as usize);
+                random_string(rng, length)
+            }
+            ColumnType::Boolean => vec![rng.gen_bool(0.5) as u8],
+            ColumnType::Date => {
+                let start = encode_date(NaiveDate::from_ymd(1970, 1, 1));
+                let end = encode_date(NaiveDate::from_ymd(2038, 1, 1));
+                rng.gen_range(start..end).to_le_bytes().to_vec()
+            }
+            ColumnType::Timestamp | ColumnType::TimestampTz => {
+                let start = encode_timestamp(NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0));
+                let end = encode_timestamp(NaiveDate::from_ymd(2038, 1, 1).and_hms(0, 0, 0));
+                rng.gen_range(start..end).to_le_bytes().to_vec()
+            }
+            ColumnType::Time => rng.gen_range(0..DAY_MICROS).to_le_bytes().to_vec(),
+            ColumnType::TimeTz => {
+                let micros = rng.gen_range(0..DAY_MICROS);
+                let time = NaiveTime::from_num_seconds_from_midnight(
+                    (micros / 1_000_000) as u32,
+                    (micros % 1_000_000 * 1000) as u32,
+                );
+                let offset = rng.gen_range(-12..=14) * 3600;
+                encode_time_tz(time, offset).to_le_bytes().to_vec()
+            }
+            ColumnType::Binary | ColumnType::Varbinary => self.random_binary(rng),
+            ColumnType::Numeric => {
+                let digits = self.length.min(MAX_NUMERIC_DIGITS);
+                let mut value = vec![0; self.width() as usize - 8];
+                value.extend_from_slice(&rng.gen_range(0..10u64.pow(digits)).to_le_bytes());
+                value
+            }
+            ColumnType::Interval => rng.gen_range(0..1000 * DAY_MICROS).to_le_bytes().to_vec(),
+            ColumnType::IntervalYearToMonth => rng.gen_range(0..1200i64).to_le_bytes().to_vec(),
+            ColumnType::Uuid => Builder::from_random_bytes(rng.gen())
+                .into_uuid()
+                .as_bytes()
+                .to_vec(),
+        };
+
+        if self.width() == u32::MAX {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        }
+
+        data.extend_from_slice(&value);
+    }
+
+    /// Random bytes for a `binary` or `varbinary` column. With the `ipaddress` conversion, they
+    /// make an IPv4 address, marked by two `ff` bytes in front of it, or an IPv6 address, which
+    /// never starts with them; with `macaddress`, they make a 6-byte MAC address in a
+    /// `varbinary` column.
+    fn random_binary(&self, rng: &mut StdRng) -> Vec<u8> {
+        let length = match (self.column_type, self.conversion) {
+            (ColumnType::Varbinary, Some(ColumnConversion::IpAddress)) => {
+                if self.length >= 16 && rng.gen_bool(0.5) {
+                    16
+                } else {
+                    6
+                }
+            }
+            (ColumnType::Varbinary, Some(ColumnConversion::MacAddress)) => 6,
+            (ColumnType::Varbinary, _) => rng.gen_range(0..=self.length),
+            _ => self.length,
+        };
+
+        let mut value: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+
+        if let Some(ColumnConversion::IpAddress) = self.conversion {
+            if length == 6 {
+                value[..2].copy_from_slice(&[0xff, 0xff]);
+            } else if value[..2] == [0xff, 0xff] {
+                value[0] = 0xfe;
+            }
+        }
+
+        value
+    }
+}
+
+/// A random string of letters and digits
+fn random_string(rng: &mut StdRng, length: usize) -> Vec<u8> {
+    rng.sample_iter(Alphanumeric).take(length).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::net::IpAddr;
+
+    use crate::args::Args;
+    use crate::column_types::ColumnTypes;
+    use crate::generate::generate;
+    use crate::validate::validate;
+    use crate::vertica_native_file::VerticaNativeFile;
+
+    fn types(file_name: &str) -> ColumnTypes {
+        ColumnTypes::from_reader(BufReader::new(File::open(file_name).unwrap())).unwrap()
+    }
+
+    fn generate_bytes(
+        types: &ColumnTypes,
+        rows: usize,
+        null_probability: f64,
+        seed: u64,
+    ) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![];
+        generate(types, rows, null_probability, seed, &mut bytes).unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn test_generated_files_are_valid() {
+        for file_name in &["data/all-valid-types.txt", "data/generate-types.txt"] {
+            let types = types(file_name);
+            let bytes = generate_bytes(&types, 50, 0.2, 1);
+            let report = validate(&mut &bytes[..]).unwrap();
+
+            assert_eq!(report.problems, vec![], "{}", file_name);
+            assert_eq!(report.rows, 50, "{}", file_name);
+        }
+    }
+
+    #[test]
+    fn test_generate_is_repeatable() {
+        let types = types("data/all-valid-types.txt");
+
+        assert_eq!(
+            generate_bytes(&types, 20, 0.1, 42),
+            generate_bytes(&types, 20, 0.1, 42)
+        );
+        assert_ne!(
+            generate_bytes(&types, 20, 0.1, 42),
+            generate_bytes(&types, 20, 0.1, 43)
+        );
+    }
+
+    #[test]
+    fn test_generated_values() {
+        let types = types("data/generate-types.txt");
+        let args = Args::with_defaults();
+        let bytes = generate_bytes(&types, 100, 0.0, 7);
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            native_file.definitions.column_widths,
+            vec![5, u32::MAX, 16, 8, 6, 16, u32::MAX, u32::MAX]
+        );
+
+        let mut rows = 0;
+
+        while let Some(row) = native_file.next_row() {
+            rows += 1;
+
+            let values: Vec<String> = (0..types.column_types.len())
+                .map(|i| {
+                    types.column_types[i].format_value(
+                        row.column_range(i).map(|r| &row.data()[r]),
+                        &types.column_conversions[i],
+                        types.precision(i),
+                        &args,
+                    )
+                })
+                .collect();
+
+            assert!(!values[0].is_empty() && values[0].len() <= 5);
+            assert!(values[1].len() <= 3);
+            assert!(values[2].parse::<u64>().unwrap() < 10_000);
+
+            for value in &values[4..7] {
+                assert!(value.parse::<IpAddr>().is_ok(), "{}", value);
+            }
+
+            assert_eq!(values[7].len(), 17);
+        }
+
+        assert_eq!(rows, 100);
+    }
+
+    #[test]
+    fn test_null_probability() {
+        let types = types("data/all-valid-types.txt");
+
+        let bytes = generate_bytes(&types, 10, 1.0, 1);
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        let mut count = 0;
+
+        while let Some(row) = native_file.next_row() {
+            assert!(row.data().is_empty());
+            count += 1;
+        }
+
+        assert_eq!(10, count);
+
+        let bytes = generate_bytes(&types, 10, 0.0, 1);
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        let mut count = 0;
+
+        while let Some(row) = native_file.next_row() {
+            assert!((0..14).all(|i| row.column_range(i).is_some()));
+            count += 1;
+        }
+
+        assert_eq!(10, count);
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+
+use crate::args::IndexArgs;
+use crate::vertica_native_file::VerticaNativeFile;
+use crate::{read_u32, read_u64};
+
+/// The signature at the start of every index file. The last character is the format's version.
+const INDEX_SIGNATURE: &[u8; 8] = b"VRINDEX1";
+
+/// The size and modification time of a native file. An index is only used while these still
+/// match, since a file that has changed may have its rows in different places.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileStamp {
+    pub size: u64,
+    /// The modification time, since the Unix epoch
+    pub modified: Duration,
+}
+
+impl FileStamp {
+    /// Get the stamp of the file at `path`.
+    pub fn of(path: &str) -> anyhow::Result<Self> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => bail!("reading metadata of [{}]: {}", path, e),
+        };
+
+        Ok(FileStamp {
+            size: metadata.len(),
+            modified: metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Where every `interval`th row of a native file starts, so reading can jump close to any row,
+/// instead of stepping over every row before it. It's kept in a sidecar file, next to the native
+/// file, named by `index_path`.
+#[derive(Debug, PartialEq)]
+pub struct RowIndex {
+    /// The stamp of the native file the index was made from
+    pub stamp: FileStamp,
+    pub interval: usize,
+    /// The number of rows in the native file
+    pub row_count: usize,
+    /// The offset of rows 1, `interval + 1`, `2 * interval + 1`, and so on, in bytes from the
+    /// start of the file
+    pub offsets: Vec<u64>,
+}
+
+/// Run the `index` subcommand, writing the index next to the input file.
+///
+/// * `args` - the subcommand's arguments
+pub fn index_file(args: &IndexArgs) -> anyhow::Result<()> {
+    if args.interval == 0 {
+        bail!("interval must be at least 1");
+    }
+
+    let stamp = FileStamp::of(&args.input)?;
+
+    let mut reader = match File::open(&args.input) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => bail!("opening input file [{}]: {}", args.input, e),
+    };
+
+    let mut native_file = VerticaNativeFile::from_reader(&mut reader).context("creating file")?;
+    let index = RowIndex::build(&mut native_file, args.interval, stamp)?;
+
+    let path = index_path(&args.input);
+
+    File::create(&path)
+        .map(BufWriter::new)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut writer| {
+            index.write(&mut writer)?;
+            Ok(writer.flush()?)
+        })
+        .with_context(|| format!("writing index file [{}]", path))?;
+
+    println!(
+        "{}: indexed {} rows, with {} offsets, in {}",
+        args.input,
+        index.row_count,
+        index.offsets.len(),
+        path
+    );
+
+    Ok(())
+}
+
+/// The name of the index file for the native file at `path`
+pub fn index_path(path: &str) -> String {
+    format!("{}.idx", path)
+}
+
+impl RowIndex {
+    /// Build an index by stepping through every row of the file, using only their lengths.
+    ///
+    /// * `native_file` - the file, positioned at its first row
+    /// * `interval` - how many rows apart the indexed rows are
+    /// * `stamp` - the stamp of the file
+    pub fn build(
+        native_file: &mut VerticaNativeFile,
+        interval: usize,
+        stamp: FileStamp,
+    ) -> anyhow::Result<Self> {
+        let mut offsets: Vec<u64> = vec![];
+
+        while native_file.next_bitfield()?.is_some() {
+            if (native_file.row_number() - 1).is_multiple_of(interval) {
+                offsets.push(native_file.row_offset());
+            }
+        }
+
+        Ok(RowIndex {
+            stamp,
+            interval,
+            row_count: native_file.row_number(),
+            offsets,
+        })
+    }
+
+    /// Read the index for the native file at `path`, if there is one. An index that was made
+    /// from a different version of the file is reported on `stderr`, and ignored.
+    pub fn for_file(path: &str) -> anyhow::Result<Option<Self>> {
+        let index_path = index_path(path);
+
+        let mut reader = match File::open(&index_path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => bail!("opening index file [{}]: {}", index_path, e),
+        };
+
+        let index = RowIndex::read(&mut reader)
+            .with_context(|| format!("reading index file [{}]", index_path))?;
+
+        if index.stamp != FileStamp::of(path)? {
+            eprintln!(
+                "ignoring index file [{}], because [{}] has changed since it was made",
+                index_path, path
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(index))
+    }
+
+    /// Find the closest indexed row at or before `row`, counting from 1. Returns the number of
+    /// rows before the indexed one, and its offset, or `None` if nothing is indexed.
+    pub fn locate(&self, row: usize) -> Option<(usize, u64)> {
+        let last = self.offsets.len().checked_sub(1)?;
+        let entry = (row.saturating_sub(1) / self.interval).min(last);
+
+        Some((entry * self.interval, self.offsets[entry]))
+    }
+
+    /// Write the index. All the numbers are little-endian, like in a native file.
+    pub fn write(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        writer.write_all(INDEX_SIGNATURE)?;
+        writer.write_all(&self.stamp.size.to_le_bytes())?;
+        writer.write_all(&self.stamp.modified.as_secs().to_le_bytes())?;
+        writer.write_all(&self.stamp.modified.subsec_nanos().to_le_bytes())?;
+        writer.write_all(&(self.interval as u64).to_le_bytes())?;
+        writer.write_all(&(self.row_count as u64).to_le_bytes())?;
+        writer.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+
+        for offset in &self.offsets {
+            writer.write_all(&offset.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an index written by `write`.
+    pub fn read(reader: &mut impl Read) -> anyhow::Result<Self> {
+        let mut signature = [0; INDEX_SIGNATURE.len()];
+        reader.read_exact(&mut signature)?;
+
+        if &signature != INDEX_SIGNATURE {
+            bail!("not an index file");
+        }
+
+        let size = read_u64(reader)?;
+        let seconds = read_u64(reader)?;
+        let nanos = read_u32(reader)?;
+        let interval = read_u64(reader)? as usize;
+        let row_count = read_u64(reader)? as usize;
+        let offset_count = read_u64(reader)? as usize;
+
+        if interval == 0 {
+            bail!("index has an interval of 0");
+        }
+
+        if offset_count != row_count.div_ceil(interval) {
+            bail!(
+                "index has {} offsets, but {} rows need {}",
+                offset_count,
+                row_count,
+                row_count.div_ceil(interval)
+            );
+        }
+
+        let offsets = (0..offset_count)
+            .map(|_| read_u64(reader))
+            .collect::<anyhow::Result<Vec<u64>>>()?;
+
+        Ok(RowIndex {
+            stamp: FileStamp {
+                size,
+                modified: Duration::new(seconds, nanos),
+            },
+            interval,
+            row_count,
+            offsets,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{BufReader, Write};
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use crate::index::{index_path, FileStamp, RowIndex};
+    use crate::vertica_native_file::VerticaNativeFile;
+
+    /// The offset of row `row` of `data/all-types-ten-rows.bin`, whose rows are all 121 bytes
+    fn ten_rows_offset(row: u64) -> u64 {
+        76 + (row - 1) * 121
+    }
+
+    fn build(interval: usize) -> RowIndex {
+        let mut reader = BufReader::new(File::open("data/all-types-ten-rows.bin").unwrap());
+        let mut native_file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+        let stamp = FileStamp {
+            size: 1286,
+            modified: Duration::new(1, 2),
+        };
+
+        RowIndex::build(&mut native_file, interval, stamp).unwrap()
+    }
+
+    #[test]
+    fn test_build_index() {
+        let index = build(3);
+
+        assert_eq!(index.row_count, 10);
+        assert_eq!(index.offsets, [1, 4, 7, 10].map(ten_rows_offset).to_vec());
+
+        assert_eq!(index.locate(1), Some((0, ten_rows_offset(1))));
+        assert_eq!(index.locate(3), Some((0, ten_rows_offset(1))));
+        assert_eq!(index.locate(4), Some((3, ten_rows_offset(4))));
+        assert_eq!(index.locate(9), Some((6, ten_rows_offset(7))));
+        assert_eq!(index.locate(500), Some((9, ten_rows_offset(10))));
+
+        assert_eq!(build(100).offsets, vec![ten_rows_offset(1)]);
+    }
+
+    #[test]
+    fn test_write_and_read_index() {
+        let index = build(4);
+
+        let mut bytes: Vec<u8> = vec![];
+        index.write(&mut bytes).unwrap();
+
+        assert_eq!(RowIndex::read(&mut &bytes[..]).unwrap(), index);
+
+        assert!(RowIndex::read(&mut &bytes[..bytes.len() - 1]).is_err());
+        assert!(RowIndex::read(&mut &b"NOTINDEX"[..]).is_err());
+    }
+
+    #[test]
+    fn test_stale_index_is_ignored() {
+        let path = format!("{}/{}.bin", temp_dir().to_str().unwrap(), Uuid::new_v4());
+        fs::copy("data/all-types-ten-rows.bin", &path).unwrap();
+
+        let mut index = build(2);
+        index.stamp = FileStamp::of(&path).unwrap();
+        index
+            .write(&mut File::create(index_path(&path)).unwrap())
+            .unwrap();
+
+        assert_eq!(RowIndex::for_file(&path).unwrap(), Some(index));
+
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[0])
+            .unwrap();
+
+        assert_eq!(RowIndex::for_file(&path).unwrap(), None);
+
+        fs::remove_file(index_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(RowIndex::for_file("data/all-types.bin").unwrap(), None);
+    }
+}
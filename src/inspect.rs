@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::io::{stdout, BufReader, Seek, SeekFrom, Write};
+
+use anyhow::{bail, Context};
+use serde_json::json;
+
+use crate::args::InspectArgs;
+use crate::column_definitions::ColumnDefinitions;
+use crate::count::count_rows;
+use crate::file_signature::FileSignature;
+use crate::vertica_native_file::VerticaNativeFile;
+
+/// What the `inspect` subcommand reports about a native file. Most of it comes straight from the
+/// header; the row count comes from walking the rows, using only their lengths.
+#[derive(Debug)]
+pub struct FileInfo {
+    pub file_name: String,
+    pub file_size: u64,
+    pub signature_valid: bool,
+    pub version: u16,
+    pub header_length: u32,
+    pub number_of_columns: u16,
+    /// The width of each column, with `u32::MAX` meaning the column is variable-width
+    pub column_widths: Vec<u32>,
+    /// The number of rows, or `None` if they couldn't be counted. They aren't counted at all if
+    /// the signature is invalid, since then there's no telling if the rest of the file is really
+    /// a native file.
+    pub row_count: Option<usize>,
+}
+
+/// Run the `inspect` subcommand, writing the report to `stdout`.
+///
+/// * `args` - the subcommand's arguments
+pub fn inspect_file(args: &InspectArgs) -> anyhow::Result<()> {
+    let info = FileInfo::from_path(&args.input)?;
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    if args.is_json {
+        info.write_json(&mut writer)
+    } else {
+        info.write_text(&mut writer)
+    }
+}
+
+impl FileInfo {
+    /// Read the header of the file at `path`, and count its rows.
+    pub fn from_path(path: &str) -> anyhow::Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => bail!("opening input file [{}]: {}", path, e),
+        };
+
+        let file_size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let signature =
+            FileSignature::from_reader_unchecked(&mut reader).context("reading signature")?;
+        let definitions =
+            ColumnDefinitions::from_reader(&mut reader).context("reading column definitions")?;
+
+        let row_count = if signature.is_valid() {
+            reader.seek(SeekFrom::Start(0))?;
+            count_file_rows(&mut reader)?
+        } else {
+            None
+        };
+
+        Ok(FileInfo {
+            file_name: path.to_string(),
+            file_size,
+            signature_valid: signature.is_valid(),
+            version: definitions.version,
+            header_length: definitions.header_length,
+            number_of_columns: definitions.number_of_columns,
+            column_widths: definitions.column_widths,
+            row_count,
+        })
+    }
+
+    /// Write the report as aligned, human-readable text.
+    pub fn write_text(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        writeln!(writer, "File:           {}", self.file_name)?;
+        writeln!(writer, "Size:           {} bytes", self.file_size)?;
+        writeln!(
+            writer,
+            "Signature:      {}",
+            if self.signature_valid {
+                "valid"
+            } else {
+                "invalid"
+            }
+        )?;
+        writeln!(writer, "Version:        {}", self.version)?;
+        writeln!(writer, "Header length:  {}", self.header_length)?;
+        writeln!(writer, "Columns:        {}", self.number_of_columns)?;
+
+        match self.row_count {
+            Some(count) => writeln!(writer, "Rows:           {}", count)?,
+            None => writeln!(writer, "Rows:           unknown")?,
+        }
+
+        writeln!(writer)?;
+        writeln!(writer, "Column  Width")?;
+
+        for (index, width) in self.column_widths.iter().enumerate() {
+            if *width == u32::MAX {
+                writeln!(writer, "{:>6}  variable", index + 1)?;
+            } else {
+                writeln!(writer, "{:>6}  {}", index + 1, width)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the report as a JSON object. Variable-width columns have a `null` width.
+    pub fn write_json(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let columns: Vec<_> = self
+            .column_widths
+            .iter()
+            .map(|width| {
+                let variable = *width == u32::MAX;
+
+                json!({
+                    "width": if variable { None } else { Some(width) },
+                    "variable_width": variable,
+                })
+            })
+            .collect();
+
+        let value = json!({
+            "file": self.file_name,
+            "file_size": self.file_size,
+            "signature_valid": self.signature_valid,
+            "version": self.version,
+            "header_length": self.header_length,
+            "column_count": self.number_of_columns,
+            "columns": columns,
+            "row_count": self.row_count,
+        });
+
+        serde_json::to_writer_pretty(&mut *writer, &value)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
+
+/// Count the rows in the file, from the start. If they can't all be read, that's reported on
+/// `stderr`, and the count is unknown.
+fn count_file_rows(reader: &mut BufReader<File>) -> anyhow::Result<Option<usize>> {
+    let mut native_file = VerticaNativeFile::from_reader(reader)?;
+
+    match count_rows(&mut native_file, false) {
+        Ok(count) => Ok(Some(count.rows)),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::inspect::FileInfo;
+
+    #[test]
+    fn test_inspect_good_file() {
+        let info = FileInfo::from_path("data/all-types.bin").unwrap();
+
+        assert!(info.signature_valid);
+        assert_eq!(info.version, 1);
+        assert_eq!(info.header_length, 61);
+        assert_eq!(info.number_of_columns, 14);
+        assert_eq!(info.column_widths[3], u32::MAX);
+        assert_eq!(info.row_count, Some(1));
+        assert_eq!(info.file_size, 197);
+
+        let info = FileInfo::from_path("data/all-types-ten-rows.bin").unwrap();
+
+        assert_eq!(info.row_count, Some(10));
+    }
+
+    #[test]
+    fn test_inspect_bad_signature() {
+        let info = FileInfo::from_path("data/all-types-with-bad-signature.bin").unwrap();
+
+        assert!(!info.signature_valid);
+        assert_eq!(info.column_widths.len(), 14);
+        assert_eq!(info.row_count, None);
+    }
+
+    #[test]
+    fn test_inspect_output() {
+        let info = FileInfo::from_path("data/all-types.bin").unwrap();
+
+        let mut text: Vec<u8> = vec![];
+        info.write_text(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+
+        assert!(text.contains("Signature:      valid\n"));
+        assert!(text.contains("Rows:           1\n"));
+        assert!(text.contains("     4  variable\n"));
+        assert!(text.contains("     5  1\n"));
+
+        let mut json: Vec<u8> = vec![];
+        info.write_json(&mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["column_count"], 14);
+        assert_eq!(json["row_count"], 1);
+        assert_eq!(json["columns"][0]["width"], 8);
+        assert_eq!(json["columns"][3]["width"], Value::Null);
+        assert_eq!(json["columns"][3]["variable_width"], true);
+    }
+}
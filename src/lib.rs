@@ -6,29 +6,49 @@ use anyhow::{bail, Context};
 use csv::Writer;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use memmap2::Mmap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use binary_encoding::BinaryEncoding;
 use column_types::ColumnTypes;
-use vertica_native_file::VerticaNativeFile;
+use filter::Filter;
+use index::RowIndex;
+use vertica_native_file::{csv_writer, write_csv_field, OwnedRow, Row, VerticaNativeFile};
 
 use crate::args::Args;
 
 pub mod args;
+pub mod assemble;
+pub mod binary_encoding;
 mod column_conversion;
 mod column_definitions;
 mod column_type;
 mod column_types;
+mod column_value;
+pub mod count;
+pub mod diff;
+pub mod explain;
 mod file_signature;
+mod filter;
+pub mod generate;
+pub mod index;
+pub mod inspect;
+pub mod profile;
+pub mod time_format;
+pub mod time_zone;
+pub mod validate;
 mod vertica_native_file;
 
-/// Read a variable number of bytes from the stream, and return it as a `Vec<u8>`
+/// Read 8 bytes from the stream, and convert it to a u64
 ///
 /// * `reader` - something implementing `Read` to read from
-/// * `length` - the number of bytes to read
-fn read_variable(reader: &mut impl Read, length: usize) -> anyhow::Result<Vec<u8>> {
-    let mut vec = vec![0u8; length];
-    reader.read_exact(vec.as_mut_slice())?;
+fn read_u64(reader: &mut impl Read) -> anyhow::Result<u64> {
+    let mut bytes: [u8; 8] = [0; 8];
 
-    Ok(vec)
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
 }
 
 /// Read 4 bytes from the stream, and convert it to a u32
@@ -64,38 +84,202 @@ fn read_u8(reader: &mut impl Read) -> anyhow::Result<u8> {
     Ok(u8::from_le_bytes(bytes))
 }
 
+/// An open input file, ready to be read as a native file. It's either memory-mapped, or read
+/// through a buffer.
+enum InputFile {
+    Mapped(Mmap),
+    Buffered(BufReader<File>),
+}
+
+impl InputFile {
+    /// Open the file at `path`, memory-mapping it if `mmap` is set. Only regular files can be
+    /// mapped; anything else (a pipe, say) falls back to being read through a buffer.
+    fn open(path: &str, mmap: bool) -> anyhow::Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => bail!("opening input file [{}]: {}", path, e),
+        };
+
+        if mmap && file.metadata()?.is_file() {
+            // Safety: the map is only ever read, but if another process truncates the file while
+            // we're reading it, we'll crash. That's the trade-off the user asks for with `--mmap`.
+            let map = unsafe { Mmap::map(&file) }.context("memory-mapping input file")?;
+            Ok(InputFile::Mapped(map))
+        } else {
+            Ok(InputFile::Buffered(BufReader::new(file)))
+        }
+    }
+
+    /// Parse the file's headers, and get ready to start retrieving rows.
+    fn native_file(&mut self) -> anyhow::Result<VerticaNativeFile<'_>> {
+        match self {
+            InputFile::Mapped(map) => VerticaNativeFile::from_bytes(map),
+            InputFile::Buffered(reader) => VerticaNativeFile::from_seekable_reader(reader),
+        }
+    }
+}
+
+/// Read the column types, names, and conversions from the types file at `path`.
+fn read_types_file(path: &str) -> anyhow::Result<ColumnTypes> {
+    let types_reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => bail!("opening types file [{}]: {}", path, e),
+    };
+
+    match ColumnTypes::from_reader(types_reader) {
+        Ok(types) => Ok(types),
+        Err(e) => bail!("parsing column types: {}", e),
+    }
+}
+
+/// Check that the types file describes as many columns as the input file has, before any rows
+/// are decoded with it.
+///
+/// * `types` - the column types, from the types file
+/// * `column_count` - the number of columns in the input file's header
+fn check_column_count(types: &ColumnTypes, column_count: usize) -> anyhow::Result<()> {
+    if types.column_types.len() != column_count {
+        bail!(
+            "types file has {} columns, but the input file has {}",
+            types.column_types.len(),
+            column_count
+        );
+    }
+
+    Ok(())
+}
+
 /// The start of the actual file processing.
 ///
 /// * `args` - all the command line arguments
 pub fn process_file(args: Args) -> anyhow::Result<()> {
-    let mut input_file = match File::open(&args.input) {
-        Ok(file) => BufReader::new(file),
-        Err(e) => bail!("opening input file [{}]: {}", args.input, e),
-    };
+    // The arguments are checked before anything is read, since skipping rows can take a while.
+    if args.every == 0 {
+        bail!("--every must be at least 1");
+    }
 
-    let types_reader = match File::open(&args.types) {
-        Ok(file) => BufReader::new(file),
-        Err(e) => bail!("opening types file [{}]: {}", args.types, e),
-    };
+    // JSON strings can only hold text, so raw binary values can't go in them.
+    if (args.is_json || args.is_json_lines) && args.binary_encoding == BinaryEncoding::Raw {
+        bail!("--binary-encoding raw only works with CSV output");
+    }
+
+    let mut input_file = InputFile::open(&args.input, args.mmap)?;
 
     // Read in the column type specification from the file. If this load fails, we abort,
     // because we can't proceed without this information.
-    let types = match ColumnTypes::from_reader(types_reader) {
-        Ok(types) => types,
-        Err(e) => {
-            bail!("parsing column types: {}", e);
+    let types = read_types_file(&args.types)?;
+
+    // This takes the input file, parses the headers, and gets ready to start retrieving rows.
+    let mut native_file = input_file.native_file().context("creating file")?;
+    native_file.skip_bad_rows = args.skip_bad_rows;
+
+    check_column_count(&types, native_file.definitions.column_widths.len())?;
+
+    // Skip to the first row that's wanted. The skipped rows are stepped over using their lengths,
+    // so this is quick, even for a lot of rows, and if the file has been indexed, most of them
+    // aren't even read.
+    let skip = match &args.rows {
+        Some(range) => {
+            native_file.last_row = range.last;
+            range.first - 1
         }
+        None => args.skip,
     };
 
-    // This line takes the input file, parses the headers, and gets ready to start retrieving
-    // rows.
-    let native_file = VerticaNativeFile::from_reader(&mut input_file).context("creating file")?;
+    if skip > 0 {
+        native_file.index = RowIndex::for_file(&args.input)?;
+        native_file.seek_to_row(skip + 1).context("skipping rows")?;
+    }
 
-    return if args.is_json || args.is_json_lines {
-        process_json_file(native_file, types, &args)
-    } else {
-        process_csv_file(native_file, types, args)
+    // Work out which columns will actually be output, and in what order.
+    let columns = types.select(&args.columns)?;
+
+    // Parse the `--where` filter up front, so mistakes in it are reported before any output is
+    // written.
+    let filter = match &args.filter {
+        Some(text) => Some(Filter::parse(text, &types).context("parsing filter")?),
+        None => None,
     };
+
+    if args.is_json || args.is_json_lines {
+        process_json_file(native_file, types, &columns, filter.as_ref(), &args)
+    } else {
+        process_csv_file(native_file, types, &columns, filter.as_ref(), args)
+    }
+}
+
+/// Go through the rows that are wanted, in order, calling `write` for each one. Those are the rows
+/// that match the filter, thinned out to every `--every`th one, and then, if `--sample` is given,
+/// to a random sample of them, up to `--limit` rows in all.
+///
+/// The sample is chosen by reservoir sampling, so the whole file only has to be read once, and
+/// only the sampled rows are kept in memory. The random number generator is seeded from `--seed`,
+/// so the same arguments always give the same sample. The sampled rows are written in the order
+/// they appear in the file.
+///
+/// * `native_file` - the Vertica native binary file
+/// * `types` - the struct containing the column type info
+/// * `filter` - the optional filter that rows must match to be output
+/// * `args` - all the other command line arguments
+/// * `write` - what to do with each row
+fn for_each_row(
+    native_file: &mut VerticaNativeFile,
+    types: &ColumnTypes,
+    filter: Option<&Filter>,
+    args: &Args,
+    mut write: impl FnMut(&Row) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let mut sample: Vec<(usize, OwnedRow)> = vec![];
+
+    let mut matched: usize = 0;
+    let mut written: usize = 0;
+
+    // Stop after `limit` rows
+    while written < args.limit {
+        let row = match native_file.next_row() {
+            Some(row) => row,
+            None => break,
+        };
+
+        // Rows that don't match the filter are dropped here, so they don't count towards
+        // `every`, `limit`, or `max_rows`.
+        if filter.is_some_and(|filter| !filter.matches(&row, types, args)) {
+            continue;
+        }
+
+        matched += 1;
+
+        if !(matched - 1).is_multiple_of(args.every) {
+            continue;
+        }
+
+        // The number of rows before this one that made it this far
+        let n = (matched - 1) / args.every;
+
+        match args.sample {
+            Some(size) if n < size => sample.push((n, row.to_owned_row())),
+            Some(size) => {
+                let slot = rng.gen_range(0..=n);
+
+                if slot < size {
+                    sample[slot] = (n, row.to_owned_row());
+                }
+            }
+            None => {
+                write(&row)?;
+                written += 1;
+            }
+        }
+    }
+
+    sample.sort_by_key(|(n, _)| *n);
+
+    for (_, row) in sample.iter().take(args.limit) {
+        write(&row.row())?;
+    }
+
+    Ok(())
 }
 
 /// Verify that the proposed output file isn't the same as either
@@ -112,7 +296,7 @@ fn validate_output_file_name_ok(args: &Args, file_name: &String) -> anyhow::Resu
         bail!("can't overwrite types file");
     }
 
-    return Ok(());
+    Ok(())
 }
 
 /// Read all the rows of the Vertica native binary file, and write them out
@@ -121,56 +305,59 @@ fn validate_output_file_name_ok(args: &Args, file_name: &String) -> anyhow::Resu
 /// * `native_file` - the Vertica native binary file
 /// * `writer` - the output; either a file, or `stdout`
 /// * `types` - the struct containing the column type info
+/// * `columns` - the indexes of the columns to output, in output order
+/// * `filter` - the optional filter that rows must match to be output
 /// * `args` - all the other command line arguments
 fn process_csv_file(
-    native_file: VerticaNativeFile,
+    mut native_file: VerticaNativeFile,
     types: ColumnTypes,
+    columns: &[usize],
+    filter: Option<&Filter>,
     args: Args,
 ) -> anyhow::Result<()> {
-    let mut writer = create_csv_file(&args, &types, None)?;
+    let mut writer = create_csv_file(&args, &types, columns, None)?;
+
+    // Every value is formatted into this one buffer before it's written out.
+    let mut scratch = String::new();
 
     let mut file_no: usize = 1;
-    // Loop over every row in the Vertica file, writing out a CSV row for each one.
-    for (i, row) in native_file.enumerate() {
-        // Stop after `limit` rows
-        if i >= args.limit {
-            break;
-        }
+    let mut i: usize = 0;
 
-        if i > 0 && i % args.max_rows == 0 {
-            writer = create_csv_file(&args, &types, Some(file_no))?;
+    // Write out a CSV row for each of the rows that are wanted.
+    for_each_row(&mut native_file, &types, filter, &args, |row| {
+        if i > 0 && i.is_multiple_of(args.max_rows) {
+            writer = create_csv_file(&args, &types, columns, Some(file_no))?;
             file_no += 1;
         }
 
-        match row.generate_csv_output(&types, args.tz_offset, &args) {
-            Ok(record) => match &writer.write_record(&record[..]) {
-                Ok(_) => {}
-                Err(e) => eprintln!("error: {}", e),
-            },
-            Err(e) => eprintln!("error: {}", e),
+        if let Err(e) = row.write_csv(&mut writer, &types, columns, &args, &mut scratch) {
+            eprintln!("error: {}", e);
         }
-    }
 
-    Ok(())
+        i += 1;
+
+        Ok(())
+    })
 }
 
 fn create_csv_file(
     args: &Args,
     types: &ColumnTypes,
+    columns: &[usize],
     iteration: Option<usize>,
 ) -> anyhow::Result<Writer<BufWriter<Box<dyn Write>>>> {
-    let base_writer = create_output_file(&args, iteration)?;
-    let mut csv_writer = csv::WriterBuilder::new()
-        .delimiter(args.delimiter)
-        .quote(if args.single_quotes { b'\'' } else { b'\"' })
-        .from_writer(base_writer);
-
-    if !args.no_header {
-        if types.has_names() {
-            match csv_writer.write_record(&types.column_names[..]) {
-                Ok(_) => {}
-                Err(e) => eprintln!("error writing CSV header: {}", e),
-            }
+    let base_writer = create_output_file(args, iteration)?;
+    let mut csv_writer = csv_writer(base_writer, args);
+
+    if !args.no_header && types.has_names() {
+        let header = columns.iter().try_for_each(|index| {
+            let name = &types.column_names[*index];
+            write_csv_field(&mut csv_writer, name.as_bytes(), false, columns.len(), args)
+        });
+
+        match header.and_then(|_| csv_writer.write_record(None::<&[u8]>)) {
+            Ok(_) => {}
+            Err(e) => eprintln!("error writing CSV header: {}", e),
         }
     }
 
@@ -181,7 +368,7 @@ fn create_json_file(
     args: &Args,
     iteration: Option<usize>,
 ) -> anyhow::Result<BufWriter<Box<dyn Write>>> {
-    let mut writer = create_output_file(&args, iteration)?;
+    let mut writer = create_output_file(args, iteration)?;
 
     // If the output is not a JSON-lines file, we will create a top-level array,
     // and include each row inside that, separated by a comma.
@@ -196,9 +383,9 @@ fn create_output_file(
     args: &Args,
     iteration: Option<usize>,
 ) -> anyhow::Result<BufWriter<Box<dyn Write>>> {
-    let output_file_name = generate_output_file_name(&args, iteration)?;
-    validate_output_file_name_ok(&args, &output_file_name)?;
-    let writer = open_output_file_name(&args, output_file_name)?;
+    let output_file_name = generate_output_file_name(args, iteration)?;
+    validate_output_file_name_ok(args, &output_file_name)?;
+    let writer = open_output_file_name(args, output_file_name)?;
 
     Ok(writer)
 }
@@ -209,10 +396,14 @@ fn create_output_file(
 /// * `native_file` - the Vertica native binary file
 /// * `writer` - the output; either a file, or `stdout`
 /// * `types` - the struct containing the column type info
+/// * `columns` - the indexes of the columns to output, in output order
+/// * `filter` - the optional filter that rows must match to be output
 /// * `args` - all the other command line arguments
 fn process_json_file(
-    native_file: VerticaNativeFile,
+    mut native_file: VerticaNativeFile,
     types: ColumnTypes,
+    columns: &[usize],
+    filter: Option<&Filter>,
     args: &Args,
 ) -> anyhow::Result<()> {
     // Unlike CSV files, which can be written without a header row containing column names, JSON
@@ -221,50 +412,51 @@ fn process_json_file(
         bail!("JSON files require column names in types file".to_string());
     }
 
-    let mut writer = create_json_file(&args, None)?;
+    let mut writer = create_json_file(args, None)?;
+
+    // Every value is formatted into this one buffer before it's written out.
+    let mut scratch = String::new();
 
     let mut file_no: usize = 1;
-    for (i, row) in native_file.enumerate() {
-        // Stop after `limit` rows
-        if i >= args.limit {
-            break;
-        }
+    let mut i: usize = 0;
 
-        if i > 0 && i % args.max_rows == 0 {
+    for_each_row(&mut native_file, &types, filter, args, |row| {
+        if i > 0 && i.is_multiple_of(args.max_rows) {
             if !args.is_json_lines {
                 write_json_row(&mut writer, "]\n".as_bytes());
             }
 
-            writer = create_json_file(&args, Some(file_no))?;
+            writer = create_json_file(args, Some(file_no))?;
             file_no += 1;
         }
 
         // If the output is not a JSON-lines file, we print a comma before every record, after
         // the first.
-        if i > 0 && !args.is_json_lines && i % args.max_rows != 0 {
+        if i > 0 && !args.is_json_lines && !i.is_multiple_of(args.max_rows) {
             write_json_row(&mut writer, ",".as_bytes());
         }
 
-        match row.generate_json_output(&types, args.tz_offset, args) {
-            Ok(record) => write_json_row(&mut writer, record.as_bytes()),
-            Err(e) => {
-                eprintln!("error: {}", e);
-                continue;
-            }
+        i += 1;
+
+        if let Err(e) = row.write_json(&mut writer, &types, columns, args, &mut scratch) {
+            eprintln!("error: {}", e);
+            return Ok(());
         }
 
         // If the output is a JSON-lines file, we need to append a newline after each object.
         if args.is_json_lines {
             write_json_row(&mut writer, "\n".as_bytes());
         }
-    }
+
+        Ok(())
+    })?;
 
     // If the output is not a JSON-lines file, we need to close the array at the end.
     if !args.is_json_lines {
         write_json_row(&mut writer, "]\n".as_bytes());
     }
 
-    return Ok(());
+    Ok(())
 }
 
 /// Generate the output file name, if none given, or return what the user specified.
@@ -398,6 +590,10 @@ mod tests {
     use serde_json::Value;
     use uuid::Uuid;
 
+    use crate::args::RowRange;
+    use crate::binary_encoding::BinaryEncoding;
+    use crate::column_types::ColumnTypes;
+    use crate::generate::generate;
     use crate::{
         generate_output_file_name, open_output_file_name, process_file,
         validate_output_file_name_ok, Args,
@@ -587,10 +783,7 @@ mod tests {
 
     #[test]
     fn test_open_impossible_file() {
-        let output_file_name = format!(
-            "/foo/bar/sdf/sdf/{}.csv",
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("/foo/bar/sdf/sdf/{}.csv", Uuid::new_v4());
 
         let args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -601,7 +794,7 @@ mod tests {
         let rc = panic::catch_unwind(|| {
             let mut file = open_output_file_name(&args, output_file_name.clone()).unwrap();
 
-            file.write("testing".as_bytes()).unwrap();
+            file.write_all("testing".as_bytes()).unwrap();
             file.flush().unwrap();
 
             let data = fs::read_to_string(output_file_name.clone()).unwrap();
@@ -625,7 +818,7 @@ mod tests {
         let rc = panic::catch_unwind(|| {
             let mut file = open_output_file_name(&args, output_file_name.clone()).unwrap();
 
-            file.write("testing\n".as_bytes()).unwrap();
+            file.write_all("testing\n".as_bytes()).unwrap();
             file.flush().unwrap();
         });
 
@@ -690,11 +883,7 @@ mod tests {
 
     #[test]
     fn test_csv_file_with_no_headers() {
-        let output_file_name = format!(
-            "{}/{}.csv",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -733,11 +922,7 @@ mod tests {
 
     #[test]
     fn test_csv_file_with_headers() {
-        let output_file_name = format!(
-            "{}/{}.csv",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -774,11 +959,7 @@ mod tests {
 
     #[test]
     fn test_csv_file_with_headers_but_turned_off() {
-        let output_file_name = format!(
-            "{}/{}.csv",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -816,13 +997,50 @@ mod tests {
     }
 
     #[test]
-    fn test_json_file_with_missing_column_names() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
+    fn test_csv_file_with_selected_columns() {
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+        let mut args = Args::with_most_defaults(
+            String::from("data/all-types.bin"),
+            Some(output_file_name.clone()),
+            String::from("data/all-valid-types-with-names.txt"),
         );
 
+        args.columns = vec![
+            "The_Date".to_string(),
+            "1".to_string(),
+            "VarCharCol".to_string(),
+        ];
+
+        let rc = panic::catch_unwind(|| {
+            let result = process_file(args);
+
+            assert!(result.is_ok());
+
+            let f = File::open(&output_file_name).unwrap();
+
+            let mut csv_file = csv::ReaderBuilder::new().has_headers(true).from_reader(f);
+
+            let headers = csv_file.headers().unwrap().clone();
+            let records: Vec<StringRecord> = csv_file.records().map(|r| r.unwrap()).collect();
+
+            assert_eq!(headers, vec!["The_Date", "IntCol", "VarCharCol"]);
+            assert_eq!(records.len(), 1_usize);
+            assert_eq!(records[0], vec!["1999-01-08", "1", "ONE"]);
+        });
+
+        match fs::remove_file(Path::new(&output_file_name)) {
+            Ok(_) => {}
+            Err(e) => eprintln!("error removing {}, {}", &output_file_name, e),
+        }
+
+        assert!(rc.is_ok());
+    }
+
+    #[test]
+    fn test_json_file_with_missing_column_names() {
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
             Some(output_file_name.clone()),
@@ -849,13 +1067,61 @@ mod tests {
     }
 
     #[test]
-    fn test_json_file() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
+    fn test_types_file_with_wrong_column_count() {
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+        let args = Args::with_most_defaults(
+            String::from("data/all-types.bin"),
+            Some(output_file_name.clone()),
+            String::from("data/id-and-name.txt"),
         );
 
+        let result = process_file(args);
+        let _ = fs::remove_file(Path::new(&output_file_name));
+
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "types file has 2 columns, but the input file has 14"
+        );
+    }
+
+    #[test]
+    fn test_every_is_checked_first() {
+        // The input doesn't exist, so this only passes if --every is checked before it's opened.
+        let mut args = Args::with_most_defaults(
+            String::from("data/no-such-file.bin"),
+            Some(String::from("-")),
+            String::from("data/all-valid-types.txt"),
+        );
+        args.every = 0;
+        args.skip = 1_000_000;
+
+        assert_eq!(
+            process_file(args).err().unwrap().to_string(),
+            "--every must be at least 1"
+        );
+    }
+
+    #[test]
+    fn test_json_file_with_raw_binary() {
+        let mut args = Args::with_most_defaults(
+            String::from("data/all-types.bin"),
+            Some(String::from("-")),
+            String::from("data/all-valid-types-with-names.txt"),
+        );
+        args.is_json = true;
+        args.binary_encoding = BinaryEncoding::Raw;
+
+        assert_eq!(
+            process_file(args).err().unwrap().to_string(),
+            "--binary-encoding raw only works with CSV output"
+        );
+    }
+
+    #[test]
+    fn test_json_file() {
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
             Some(output_file_name.clone()),
@@ -873,7 +1139,7 @@ mod tests {
 
             assert_eq!(contents[0]["IntCol"].as_i64().unwrap(), 1);
             assert_eq!(contents[0]["The_Date"].as_str().unwrap(), "1999-01-08");
-            assert_eq!(contents[0]["Bools"].as_bool().unwrap(), true);
+            assert!(contents[0]["Bools"].as_bool().unwrap());
         });
 
         match fs::remove_file(Path::new(&output_file_name)) {
@@ -885,12 +1151,42 @@ mod tests {
     }
 
     #[test]
-    fn test_gzipped_csv_file_with_headers() {
-        let output_file_name = format!(
-            "{}/{}.csv",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
+    fn test_json_file_with_selected_columns() {
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+        let mut args = Args::with_most_defaults(
+            String::from("data/all-types.bin"),
+            Some(output_file_name.clone()),
+            String::from("data/all-valid-types-with-names.txt"),
         );
+        args.is_json = true;
+        args.columns = vec!["Bools".to_string(), "IntCol".to_string()];
+
+        let rc = panic::catch_unwind(|| {
+            let result = process_file(args);
+
+            assert!(result.is_ok());
+            let f = File::open(&output_file_name).unwrap();
+
+            let contents: Value = serde_json::from_reader(f).unwrap();
+            let row = contents[0].as_object().unwrap();
+
+            assert_eq!(row.len(), 2);
+            assert_eq!(row["IntCol"].as_i64().unwrap(), 1);
+            assert!(row["Bools"].as_bool().unwrap());
+        });
+
+        match fs::remove_file(Path::new(&output_file_name)) {
+            Ok(_) => {}
+            Err(e) => eprintln!("error removing {}, {}", &output_file_name, e),
+        }
+
+        assert!(rc.is_ok());
+    }
+
+    #[test]
+    fn test_gzipped_csv_file_with_headers() {
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -929,11 +1225,7 @@ mod tests {
 
     #[test]
     fn test_gzipped_json_file() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types-ten-rows.bin"),
@@ -966,11 +1258,7 @@ mod tests {
 
     #[test]
     fn test_json_lines_file() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types.bin"),
@@ -989,7 +1277,7 @@ mod tests {
 
             assert_eq!(contents["IntCol"].as_i64().unwrap(), 1);
             assert_eq!(contents["The_Date"].as_str().unwrap(), "1999-01-08");
-            assert_eq!(contents["Bools"].as_bool().unwrap(), true);
+            assert!(contents["Bools"].as_bool().unwrap());
         });
 
         match fs::remove_file(Path::new(&output_file_name)) {
@@ -1002,11 +1290,7 @@ mod tests {
 
     #[test]
     fn test_csv_file_row_limit() {
-        let output_file_name = format!(
-            "{}/{}.csv",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types-ten-rows.bin"),
@@ -1044,12 +1328,192 @@ mod tests {
     }
 
     #[test]
-    fn test_json_lines_with_row_limit() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
+    fn test_csv_file_with_filter_and_row_limit() {
+        for (filter, expected_rows) in [("IntCol = 1", 3_usize), ("IntCol = 2", 0_usize)] {
+            let output_file_name =
+                format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+            let mut args = Args::with_most_defaults(
+                String::from("data/all-types-ten-rows.bin"),
+                Some(output_file_name.clone()),
+                String::from("data/all-valid-types-with-names.txt"),
+            );
+
+            args.filter = Some(filter.to_string());
+            args.limit = 3_usize;
+
+            let rc = panic::catch_unwind(|| {
+                let result = process_file(args);
+
+                assert!(result.is_ok());
+
+                let f = File::open(&output_file_name).unwrap();
+
+                let mut csv_file = csv::ReaderBuilder::new().has_headers(true).from_reader(f);
+
+                let records: Vec<StringRecord> = csv_file.records().map(|r| r.unwrap()).collect();
+
+                assert_eq!(records.len(), expected_rows);
+            });
+
+            match fs::remove_file(Path::new(&output_file_name)) {
+                Ok(_) => {}
+                Err(e) => eprintln!("error removing {}, {}", &output_file_name, e),
+            }
+
+            assert!(rc.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_csv_file_with_skipped_rows_and_row_range() {
+        let cases = [
+            (8, None, usize::MAX, 2_usize),
+            (20, None, usize::MAX, 0),
+            (0, Some(RowRange { first: 3, last: 4 }), usize::MAX, 2),
+            (
+                0,
+                Some(RowRange {
+                    first: 9,
+                    last: usize::MAX,
+                }),
+                usize::MAX,
+                2,
+            ),
+            (0, Some(RowRange { first: 2, last: 8 }), 3, 3),
+        ];
+
+        for (skip, rows, limit, expected_rows) in cases {
+            let output_file_name =
+                format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+            let mut args = Args::with_most_defaults(
+                String::from("data/all-types-ten-rows.bin"),
+                Some(output_file_name.clone()),
+                String::from("data/all-valid-types-with-names.txt"),
+            );
+
+            args.skip = skip;
+            args.rows = rows;
+            args.limit = limit;
+
+            let rc = panic::catch_unwind(|| {
+                let result = process_file(args);
+
+                assert!(result.is_ok());
+
+                let f = File::open(&output_file_name).unwrap();
+
+                let mut csv_file = csv::ReaderBuilder::new().has_headers(true).from_reader(f);
+
+                let records: Vec<StringRecord> = csv_file.records().map(|r| r.unwrap()).collect();
+
+                assert_eq!(records.len(), expected_rows);
+            });
+
+            match fs::remove_file(Path::new(&output_file_name)) {
+                Ok(_) => {}
+                Err(e) => eprintln!("error removing {}, {}", &output_file_name, e),
+            }
+
+            assert!(rc.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_csv_file_with_every_and_sample() {
+        let types_file = String::from("data/all-valid-types-with-names.txt");
+        let input_file = format!("{}/{}.bin", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+        let types = ColumnTypes::from_reader(BufReader::new(File::open(&types_file).unwrap()));
+        generate(
+            &types.unwrap(),
+            50,
+            0.1,
+            1,
+            &mut File::create(&input_file).unwrap(),
+        )
+        .unwrap();
+
+        let records = |every: usize, sample: Option<usize>, seed: u64, limit: usize| {
+            let output_file_name =
+                format!("{}/{}.csv", temp_dir().to_str().unwrap(), Uuid::new_v4());
+
+            let mut args = Args::with_most_defaults(
+                input_file.clone(),
+                Some(output_file_name.clone()),
+                types_file.clone(),
+            );
+
+            args.every = every;
+            args.sample = sample;
+            args.seed = seed;
+            args.limit = limit;
+
+            process_file(args).unwrap();
+
+            let f = File::open(&output_file_name).unwrap();
+            let mut csv_file = csv::ReaderBuilder::new().has_headers(true).from_reader(f);
+            let records: Vec<StringRecord> = csv_file.records().map(|r| r.unwrap()).collect();
+
+            fs::remove_file(&output_file_name).unwrap();
+
+            records
+        };
+
+        let rc = panic::catch_unwind(|| {
+            let all = records(1, None, 0, usize::MAX);
+            assert_eq!(all.len(), 50);
+
+            let every = records(3, None, 0, usize::MAX);
+            let expected: Vec<StringRecord> = all.iter().step_by(3).cloned().collect();
+            assert_eq!(every.len(), 17);
+            assert_eq!(every, expected);
+
+            // The sample comes out in file order, and is the same every time for the same seed.
+            let sample = records(1, Some(5), 7, usize::MAX);
+            let positions: Vec<usize> = sample
+                .iter()
+                .map(|r| all.iter().position(|a| a == r).unwrap())
+                .collect();
+
+            assert_eq!(sample.len(), 5);
+            assert!(positions.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(sample, records(1, Some(5), 7, usize::MAX));
+            assert_ne!(sample, records(1, Some(5), 8, usize::MAX));
+            assert_eq!(sample[..2], records(1, Some(5), 7, 2)[..]);
+
+            assert_eq!(records(1, Some(100), 7, usize::MAX), all);
+            assert_eq!(records(5, Some(3), 7, usize::MAX).len(), 3);
+        });
+
+        fs::remove_file(&input_file).unwrap();
+
+        assert!(rc.is_ok());
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        let mut args = Args::with_most_defaults(
+            String::from("data/all-types.bin"),
+            Some("-".to_string()),
+            String::from("data/all-valid-types-with-names.txt"),
+        );
+
+        args.filter = Some("NoSuchColumn = 1".to_string());
+
+        let result = process_file(args);
+
+        assert!(result.is_err());
+        assert_eq!(
+            format!("{:#}", result.unwrap_err()),
+            "parsing filter: no such column in filter: NoSuchColumn"
         );
+    }
+
+    #[test]
+    fn test_json_lines_with_row_limit() {
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types-ten-rows.bin"),
@@ -1073,7 +1537,7 @@ mod tests {
             assert_eq!(contents.len(), 5_usize);
             assert_eq!(contents[0]["IntCol"].as_i64().unwrap(), 1);
             assert_eq!(contents[0]["The_Date"].as_str().unwrap(), "1999-01-08");
-            assert_eq!(contents[0]["Bools"].as_bool().unwrap(), true);
+            assert!(contents[0]["Bools"].as_bool().unwrap());
         });
 
         match fs::remove_file(Path::new(&output_file_name)) {
@@ -1086,11 +1550,7 @@ mod tests {
 
     #[test]
     fn test_json_file_with_row_limit() {
-        let output_file_name = format!(
-            "{}/{}.json",
-            temp_dir().to_str().unwrap(),
-            Uuid::new_v4().to_string()
-        );
+        let output_file_name = format!("{}/{}.json", temp_dir().to_str().unwrap(), Uuid::new_v4());
 
         let mut args = Args::with_most_defaults(
             String::from("data/all-types-ten-rows.bin"),
@@ -1112,7 +1572,7 @@ mod tests {
             assert_eq!(contents.as_array().unwrap().len(), 5_usize);
             assert_eq!(contents[0]["IntCol"].as_i64().unwrap(), 1);
             assert_eq!(contents[0]["The_Date"].as_str().unwrap(), "1999-01-08");
-            assert_eq!(contents[0]["Bools"].as_bool().unwrap(), true);
+            assert!(contents[0]["Bools"].as_bool().unwrap());
         });
 
         match fs::remove_file(Path::new(&output_file_name)) {
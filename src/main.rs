@@ -1,14 +1,32 @@
 use std::process::exit;
 
-use clap::Parser;
-
-use verticareader::args::Args;
+use verticareader::args::{Command, Invocation};
+use verticareader::assemble::assemble_file;
+use verticareader::count::count_file;
+use verticareader::diff::diff_files;
+use verticareader::explain::explain_file;
+use verticareader::generate::generate_file;
+use verticareader::index::index_file;
+use verticareader::inspect::inspect_file;
 use verticareader::process_file;
+use verticareader::profile::profile_file;
+use verticareader::validate::validate_file;
 
 fn main() {
-    let args = Args::parse();
+    let result = match Invocation::parse() {
+        Invocation::Convert(args) => process_file(*args),
+        Invocation::Command(Command::Inspect(args)) => inspect_file(&args),
+        Invocation::Command(Command::Assemble(args)) => assemble_file(&args),
+        Invocation::Command(Command::Count(args)) => count_file(&args),
+        Invocation::Command(Command::Diff(args)) => diff_files(&args),
+        Invocation::Command(Command::Explain(args)) => explain_file(&args),
+        Invocation::Command(Command::Generate(args)) => generate_file(&args),
+        Invocation::Command(Command::Index(args)) => index_file(&args),
+        Invocation::Command(Command::Profile(args)) => profile_file(&args),
+        Invocation::Command(Command::Validate(args)) => validate_file(&args),
+    };
 
-    match process_file(args) {
+    match result {
         Ok(_) => {}
         Err(e) => {
             eprintln!("Error: {}", e);
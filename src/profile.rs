@@ -0,0 +1,672 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::hash::{Hash, Hasher};
+use std::io::{stdout, Write};
+
+use anyhow::Context;
+use serde_json::json;
+
+use crate::args::{Args, ProfileArgs};
+use crate::column_type::ColumnType;
+use crate::column_types::ColumnTypes;
+use crate::column_value::ColumnValue;
+use crate::vertica_native_file::{Row, VerticaNativeFile};
+use crate::{check_column_count, read_types_file, InputFile};
+
+/// The number of bits of each value's hash that pick its register, when estimating the number
+/// of distinct values. 12 bits gives 4096 registers, and estimates that are usually within 2%.
+const DISTINCT_BITS: u32 = 12;
+
+/// The most different values tracked for each column, when finding its most frequent values. If
+/// a column has no more different values than this, its counts are exact.
+const FREQUENT_VALUES_CAPACITY: usize = 10_000;
+
+/// The longest a value can be in the text report, before it's cut short
+const MAX_TEXT_VALUE_LENGTH: usize = 30;
+
+/// What the `profile` subcommand reports about each column of a native file.
+#[derive(Debug, PartialEq)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub column_type: String,
+    pub nulls: usize,
+    /// The number of distinct values that aren't null
+    pub distinct: usize,
+    /// Whether `distinct` and the counts in `top` are exact, rather than estimates; they're only
+    /// estimates for columns with a lot of different values
+    pub exact: bool,
+    /// The smallest and largest values, formatted like they are when converting; for dates and
+    /// times, these give the range they cover
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// The mean, for integers and floats
+    pub mean: Option<f64>,
+    /// The shortest and longest values, in bytes, for strings and binary values
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// The most frequent values, and how often they appear, most frequent first
+    pub top: Vec<(String, usize)>,
+}
+
+/// The result of profiling a native file.
+#[derive(Debug)]
+pub struct Profile {
+    pub rows: usize,
+    pub columns: Vec<ColumnProfile>,
+}
+
+/// Run the `profile` subcommand, writing the report to `stdout`.
+///
+/// * `args` - the subcommand's arguments
+pub fn profile_file(args: &ProfileArgs) -> anyhow::Result<()> {
+    let mut input_file = InputFile::open(&args.input, args.mmap)?;
+    let mut native_file = input_file.native_file().context("creating file")?;
+
+    let types = read_types_file(&args.types)?;
+    check_column_count(&types, native_file.definitions.column_widths.len())?;
+
+    let profile = profile(&mut native_file, &types, args.top)?;
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    if args.is_json {
+        profile.write_json(&mut writer)
+    } else {
+        profile.write_text(&mut writer)
+    }
+}
+
+/// Read every row of the file, once, and work out the statistics for each column. Only a fixed
+/// amount of memory is used for each column, however many rows there are, so the distinct
+/// counts and most frequent values are estimates for columns with a lot of different values.
+///
+/// A row that can't be read is an error, rather than the end of the file, so a damaged file
+/// never passes for a shorter one.
+///
+/// * `native_file` - the file, positioned at its first row
+/// * `types` - the column types
+/// * `top` - how many of the most frequent values to report for each column
+pub fn profile(
+    native_file: &mut VerticaNativeFile,
+    types: &ColumnTypes,
+    top: usize,
+) -> anyhow::Result<Profile> {
+    let mut stats: Vec<ColumnStats> = types
+        .column_types
+        .iter()
+        .map(|_| ColumnStats::new())
+        .collect();
+
+    let mut rows: usize = 0;
+
+    while let Some(row) = native_file.read_row()? {
+        rows += 1;
+
+        for (index, null) in row.null_values().enumerate() {
+            if null {
+                stats[index].nulls += 1;
+            } else {
+                stats[index].add(&types.column_types[index], &row, index);
+            }
+        }
+    }
+
+    let args = Args::with_defaults();
+
+    let columns = stats
+        .into_iter()
+        .enumerate()
+        .map(|(index, stats)| stats.finish(types, index, top, &args))
+        .collect();
+
+    Ok(Profile { rows, columns })
+}
+
+/// The running statistics for one column.
+struct ColumnStats {
+    nulls: usize,
+    /// The number of values that aren't null
+    values: usize,
+    /// The raw bytes of the smallest and largest values so far
+    min: Option<Vec<u8>>,
+    max: Option<Vec<u8>>,
+    /// The sum of the values so far, for the mean
+    sum: f64,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    distinct: DistinctCounter,
+    frequent: FrequentValues,
+}
+
+impl ColumnStats {
+    fn new() -> Self {
+        ColumnStats {
+            nulls: 0,
+            values: 0,
+            min: None,
+            max: None,
+            sum: 0.0,
+            min_length: None,
+            max_length: None,
+            distinct: DistinctCounter::new(),
+            frequent: FrequentValues::new(FREQUENT_VALUES_CAPACITY),
+        }
+    }
+
+    /// Add the value of the column at `index` in `row`, which isn't null.
+    fn add(&mut self, column_type: &ColumnType, row: &Row, index: usize) {
+        let bytes = row.column(index).unwrap_or_default();
+        let value = column_type.decode(Some(bytes));
+
+        self.values += 1;
+        self.distinct.add(bytes);
+        self.frequent.add(bytes);
+
+        match value {
+            ColumnValue::Integer(i) => self.sum += i as f64,
+            ColumnValue::Float(f) => self.sum += f,
+            _ => {}
+        }
+
+        let length = match value {
+            ColumnValue::String(s) => Some(s.len()),
+            ColumnValue::Binary(b) => Some(b.len()),
+            _ => None,
+        };
+
+        if let Some(length) = length {
+            self.min_length = Some(self.min_length.map_or(length, |l| l.min(length)));
+            self.max_length = Some(self.max_length.map_or(length, |l| l.max(length)));
+        }
+
+        if replaces(column_type, &value, &self.min, Ordering::Less) {
+            self.min = Some(bytes.to_vec());
+        }
+
+        if replaces(column_type, &value, &self.max, Ordering::Greater) {
+            self.max = Some(bytes.to_vec());
+        }
+    }
+
+    /// Turn the statistics into the report for the column at `index`, formatting the values the
+    /// same way they are when converting.
+    fn finish(self, types: &ColumnTypes, index: usize, top: usize, args: &Args) -> ColumnProfile {
+        let column_type = &types.column_types[index];
+        let format = |bytes: &[u8]| {
+            column_type.format_value(
+                Some(bytes),
+                &types.column_conversions[index],
+                types.precision(index),
+                args,
+            )
+        };
+
+        let mean = match column_type {
+            ColumnType::Integer | ColumnType::Float if self.values > 0 => {
+                Some(self.sum / self.values as f64)
+            }
+            _ => None,
+        };
+
+        let (distinct, exact) = if self.frequent.overflowed {
+            (self.distinct.estimate(), false)
+        } else {
+            (self.frequent.counts.len(), true)
+        };
+
+        ColumnProfile {
+            name: types.column_names[index].clone(),
+            column_type: format!("{:?}", column_type),
+            nulls: self.nulls,
+            distinct,
+            exact,
+            min: self.min.as_deref().map(format),
+            max: self.max.as_deref().map(format),
+            mean,
+            min_length: self.min_length,
+            max_length: self.max_length,
+            top: self
+                .frequent
+                .top(top)
+                .into_iter()
+                .map(|(bytes, count)| (format(bytes), count))
+                .collect(),
+        }
+    }
+}
+
+/// Whether `value` should replace `current`, the raw bytes of the smallest or largest value so far,
+/// because it's ordered `wanted` compared to it. A value that can't be ordered, like a NaN, never
+/// replaces anything.
+fn replaces(
+    column_type: &ColumnType,
+    value: &ColumnValue,
+    current: &Option<Vec<u8>>,
+    wanted: Ordering,
+) -> bool {
+    match current {
+        Some(current) => compare(value, &column_type.decode(Some(current))) == Some(wanted),
+        None => compare(value, value).is_some(),
+    }
+}
+
+/// Compare two values of the same type, or return `None` if they can't be ordered, like a NaN.
+/// A `timetz` is compared by its time in UTC, and a `numeric` by its words, most significant
+/// first. Infinite dates and timestamps come after, or before, all the others, and ones too far
+/// away for chrono come after, or before, all the ones that aren't.
+fn compare(a: &ColumnValue, b: &ColumnValue) -> Option<Ordering> {
+    match (a, b) {
+        (ColumnValue::Infinity, ColumnValue::Infinity)
+        | (ColumnValue::NegativeInfinity, ColumnValue::NegativeInfinity) => Some(Ordering::Equal),
+        (ColumnValue::Infinity, _) | (_, ColumnValue::NegativeInfinity) => Some(Ordering::Greater),
+        (ColumnValue::NegativeInfinity, _) | (_, ColumnValue::Infinity) => Some(Ordering::Less),
+        (ColumnValue::OutOfRange(a), ColumnValue::OutOfRange(b)) => a.partial_cmp(b),
+        (ColumnValue::OutOfRange(a), _) => Some(a.cmp(&0)),
+        (_, ColumnValue::OutOfRange(b)) => Some(0.cmp(b)),
+        (ColumnValue::Integer(a), ColumnValue::Integer(b)) => a.partial_cmp(b),
+        (ColumnValue::Float(a), ColumnValue::Float(b)) => a.partial_cmp(b),
+        (ColumnValue::String(a), ColumnValue::String(b)) => a.partial_cmp(b),
+        (ColumnValue::Boolean(a), ColumnValue::Boolean(b)) => a.partial_cmp(b),
+        (ColumnValue::Date(a), ColumnValue::Date(b)) => a.partial_cmp(b),
+        (ColumnValue::Timestamp(a), ColumnValue::Timestamp(b)) => a.partial_cmp(b),
+        (ColumnValue::TimestampTz(a), ColumnValue::TimestampTz(b)) => a.partial_cmp(b),
+        (ColumnValue::Time(a), ColumnValue::Time(b)) => a.partial_cmp(b),
+        (ColumnValue::TimeTz(a, _), ColumnValue::TimeTz(b, _)) => a.partial_cmp(b),
+        (ColumnValue::Binary(a), ColumnValue::Binary(b)) => a.partial_cmp(b),
+        (ColumnValue::Numeric(a), ColumnValue::Numeric(b)) => words(a).partial_cmp(&words(b)),
+        (ColumnValue::Interval(a), ColumnValue::Interval(b)) => a.partial_cmp(b),
+        (ColumnValue::IntervalYearToMonth(a), ColumnValue::IntervalYearToMonth(b)) => {
+            a.partial_cmp(b)
+        }
+        (ColumnValue::Uuid(a), ColumnValue::Uuid(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+/// The words of a `numeric`, most significant first
+fn words(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+/// An estimate of the number of distinct values, using HyperLogLog. It takes the same small
+/// amount of memory, however many values there are.
+struct DistinctCounter {
+    registers: Vec<u8>,
+}
+
+impl DistinctCounter {
+    fn new() -> Self {
+        DistinctCounter {
+            registers: vec![0; 1 << DISTINCT_BITS],
+        }
+    }
+
+    /// Add a value. The top bits of its hash pick a register, which keeps the longest run of
+    /// leading zeros seen in the rest of the hashes that land in it.
+    fn add(&mut self, bytes: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let register = (hash >> (64 - DISTINCT_BITS)) as usize;
+        let rank = ((hash << DISTINCT_BITS).leading_zeros() + 1).min(64 - DISTINCT_BITS + 1) as u8;
+
+        self.registers[register] = self.registers[register].max(rank);
+    }
+
+    /// The estimated number of distinct values added so far. Small counts, where a lot of the
+    /// registers are still empty, are estimated from how many are empty instead.
+    fn estimate(&self) -> usize {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+
+        let estimate = alpha * m * m / sum;
+        let empty = self.registers.iter().filter(|r| **r == 0).count();
+
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+}
+
+/// The most frequent values, found with the Misra-Gries algorithm. Up to `capacity` different
+/// values are counted exactly. After that, a new value makes every count go down by one, and
+/// the values whose counts reach zero are dropped, which makes room for more. Any value that
+/// makes up more than 1/`capacity` of the total is sure to still be there at the end, but its
+/// count may be too low.
+struct FrequentValues {
+    counts: HashMap<Vec<u8>, usize>,
+    capacity: usize,
+    /// Whether there have ever been more different values than `capacity`, so the counts are
+    /// no longer exact
+    overflowed: bool,
+}
+
+impl FrequentValues {
+    fn new(capacity: usize) -> Self {
+        FrequentValues {
+            counts: HashMap::new(),
+            capacity,
+            overflowed: false,
+        }
+    }
+
+    fn add(&mut self, bytes: &[u8]) {
+        if let Some(count) = self.counts.get_mut(bytes) {
+            *count += 1;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(bytes.to_vec(), 1);
+        } else {
+            self.overflowed = true;
+            self.counts.retain(|_, count| {
+                *count -= 1;
+                *count > 0
+            });
+        }
+    }
+
+    /// The `n` values with the highest counts, highest first. Values with the same count are in
+    /// byte order, so the result doesn't depend on the order of the hash map.
+    fn top(&self, n: usize) -> Vec<(&[u8], usize)> {
+        let mut top: Vec<(&[u8], usize)> = self
+            .counts
+            .iter()
+            .map(|(bytes, count)| (bytes.as_slice(), *count))
+            .collect();
+
+        top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(n);
+
+        top
+    }
+}
+
+impl Profile {
+    /// Write the report as text; a table of the statistics for each column, and then the most
+    /// frequent values of each column. Long values are cut short.
+    pub fn write_text(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        writeln!(writer, "Rows:  {}", self.rows)?;
+        writeln!(writer)?;
+
+        let mut table: Vec<Vec<String>> = vec![[
+            "Column", "Type", "Nulls", "Distinct", "Min", "Max", "Mean", "Length",
+        ]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()];
+
+        for (index, column) in self.columns.iter().enumerate() {
+            let length = match (column.min_length, column.max_length) {
+                (Some(min), Some(max)) => format!("{}-{}", min, max),
+                _ => String::new(),
+            };
+
+            table.push(vec![
+                label(column, index),
+                column.column_type.clone(),
+                column.nulls.to_string(),
+                if column.exact {
+                    column.distinct.to_string()
+                } else {
+                    format!("~{}", column.distinct)
+                },
+                shorten(column.min.as_deref().unwrap_or_default()),
+                shorten(column.max.as_deref().unwrap_or_default()),
+                column.mean.map(format_mean).unwrap_or_default(),
+                length,
+            ]);
+        }
+
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+            .collect();
+
+        for row in &table {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+
+            writeln!(writer, "{}", cells.join("  ").trim_end())?;
+        }
+
+        for (index, column) in self.columns.iter().enumerate() {
+            if column.top.is_empty() {
+                continue;
+            }
+
+            writeln!(writer)?;
+            writeln!(
+                writer,
+                "Most frequent values of {}{}",
+                label(column, index),
+                if column.exact { "" } else { " (approximate)" }
+            )?;
+
+            for (value, count) in &column.top {
+                writeln!(writer, "{:>10}  {}", count, shorten(value))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the report as a JSON object, with the full values.
+    pub fn write_json(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let columns: Vec<_> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let top: Vec<_> = column
+                    .top
+                    .iter()
+                    .map(|(value, count)| json!({ "value": value, "count": count }))
+                    .collect();
+
+                json!({
+                    "column": index + 1,
+                    "name": Some(&column.name).filter(|n| !n.is_empty()),
+                    "type": column.column_type,
+                    "nulls": column.nulls,
+                    "distinct": column.distinct,
+                    "exact": column.exact,
+                    "min": column.min,
+                    "max": column.max,
+                    "mean": column.mean,
+                    "min_length": column.min_length,
+                    "max_length": column.max_length,
+                    "top": top,
+                })
+            })
+            .collect();
+
+        let value = json!({ "rows": self.rows, "columns": columns });
+
+        serde_json::to_writer_pretty(&mut *writer, &value)?;
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
+
+/// The column's name, or its position, counting from 1, if it doesn't have a name.
+fn label(column: &ColumnProfile, index: usize) -> String {
+    if column.name.is_empty() {
+        (index + 1).to_string()
+    } else {
+        column.name.clone()
+    }
+}
+
+/// The mean, rounded to 6 decimal places, without any trailing zeros
+fn format_mean(mean: f64) -> String {
+    let rounded = format!("{:.6}", mean);
+
+    rounded
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The value, cut short if it's too long for the text report
+fn shorten(value: &str) -> String {
+    if value.chars().count() <= MAX_TEXT_VALUE_LENGTH {
+        value.to_string()
+    } else {
+        let start: String = value.chars().take(MAX_TEXT_VALUE_LENGTH - 3).collect();
+        format!("{}...", start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::BufReader;
+
+    use serde_json::Value;
+
+    use crate::column_types::ColumnTypes;
+    use crate::profile::{profile, DistinctCounter, FrequentValues, Profile};
+    use crate::vertica_native_file::VerticaNativeFile;
+
+    fn profile_file(file_name: &str) -> Profile {
+        let types = ColumnTypes::from_reader(BufReader::new(
+            File::open("data/all-valid-types-with-names.txt").unwrap(),
+        ))
+        .unwrap();
+
+        let mut reader = BufReader::new(File::open(file_name).unwrap());
+        let mut native_file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+
+        profile(&mut native_file, &types, 3).unwrap()
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = profile_file("data/all-types-ten-rows.bin");
+
+        assert_eq!(profile.rows, 10);
+
+        let int_col = &profile.columns[0];
+        assert_eq!(int_col.name, "IntCol");
+        assert_eq!(int_col.nulls, 0);
+        assert_eq!(int_col.distinct, 1);
+        assert!(int_col.exact);
+        assert_eq!(int_col.min.as_deref(), Some("1"));
+        assert_eq!(int_col.max.as_deref(), Some("1"));
+        assert_eq!(int_col.mean, Some(1.0));
+        assert_eq!(int_col.min_length, None);
+        assert_eq!(int_col.top, vec![("1".to_string(), 10)]);
+
+        let varchar_col = &profile.columns[3];
+        assert_eq!(varchar_col.min_length, Some(3));
+        assert_eq!(varchar_col.max_length, Some(3));
+        assert_eq!(varchar_col.mean, None);
+
+        let date_col = &profile.columns[5];
+        assert_eq!(date_col.min.as_deref(), Some("1999-01-08"));
+        assert_eq!(date_col.max.as_deref(), Some("1999-01-08"));
+
+        let profile = profile_file("data/all-types-with-nulls.bin");
+        let float_col = &profile.columns[1];
+
+        assert_eq!(float_col.nulls, 1);
+        assert_eq!(float_col.distinct, 0);
+        assert_eq!(float_col.min, None);
+        assert_eq!(float_col.mean, None);
+        assert_eq!(float_col.top, vec![]);
+    }
+
+    #[test]
+    fn test_profile_bad_row() {
+        let types = ColumnTypes::from_reader(BufReader::new(
+            File::open("data/all-valid-types-with-names.txt").unwrap(),
+        ))
+        .unwrap();
+
+        // Make the `varchar` in row 5 longer than the row.
+        let mut bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+        bytes[592..596].copy_from_slice(&1000u32.to_le_bytes());
+        let mut native_file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+
+        let message = profile(&mut native_file, &types, 3)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(message.starts_with("row 5 at offset 560: "), "{}", message);
+    }
+
+    #[test]
+    fn test_distinct_counter() {
+        for count in [10_u64, 1000, 100_000] {
+            let mut counter = DistinctCounter::new();
+
+            for value in 0..count {
+                counter.add(&value.to_le_bytes());
+                counter.add(&value.to_le_bytes());
+            }
+
+            let error = (counter.estimate() as f64 - count as f64).abs() / count as f64;
+            assert!(
+                error < 0.05,
+                "{} estimated as {}",
+                count,
+                counter.estimate()
+            );
+        }
+    }
+
+    #[test]
+    fn test_frequent_values() {
+        let mut frequent = FrequentValues::new(3);
+
+        for value in ["a", "b", "a", "c", "a", "b"] {
+            frequent.add(value.as_bytes());
+        }
+
+        assert!(!frequent.overflowed);
+        assert_eq!(frequent.top(2), vec![(&b"a"[..], 3), (&b"b"[..], 2)]);
+
+        // A fourth value pushes every count down, and drops the values that reach zero, but the
+        // most frequent value survives.
+        frequent.add(b"d");
+
+        assert!(frequent.overflowed);
+        assert_eq!(frequent.top(5), vec![(&b"a"[..], 2), (&b"b"[..], 1)]);
+    }
+
+    #[test]
+    fn test_profile_output() {
+        let profile = profile_file("data/all-types-with-nulls.bin");
+
+        let mut text: Vec<u8> = vec![];
+        profile.write_text(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+
+        assert!(text.starts_with("Rows:  1\n\nColumn "));
+        assert!(text.contains("\nIntCol         Integer      0      1         1 "));
+        assert!(text.contains("\nMost frequent values of VarCharCol\n         1  ONE\n"));
+
+        let mut json: Vec<u8> = vec![];
+        profile.write_json(&mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["rows"], 1);
+        assert_eq!(json["columns"][1]["name"], "FloatCol");
+        assert_eq!(json["columns"][1]["nulls"], 1);
+        assert_eq!(json["columns"][1]["min"], Value::Null);
+        assert_eq!(json["columns"][3]["top"][0]["value"], "ONE");
+        assert_eq!(json["columns"][3]["top"][0]["count"], 1);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone as _, Timelike};
+
+use crate::args::Args;
+use crate::column_conversion::ColumnConversion;
+use crate::column_type::{write_fraction, write_offset};
+use crate::column_value::ColumnValue;
+
+/// How dates, times, and timestamps are written out, when not in their usual forms. It's given
+/// for a column in the types file, after its name, or for every column with `--time-format`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeFormat {
+    /// ISO 8601, like `2023-01-02T03:04:05`; only values with a time zone have an offset
+    Iso8601,
+    /// RFC 3339, like `2023-01-02T03:04:05Z`; values without a time zone are taken to be in UTC
+    Rfc3339,
+    /// Whole seconds since the Unix epoch
+    EpochSeconds,
+    /// Milliseconds since the Unix epoch
+    EpochMilliseconds,
+    /// Microseconds since the Unix epoch
+    EpochMicroseconds,
+    /// A `strftime` format, like `%m/%d/%Y`
+    Strftime(String),
+}
+
+/// A date or time, decoded and ready to be formatted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Temporal {
+    Date(NaiveDate),
+    /// A local time, and its offset in seconds east of UTC, if it has one
+    Time(NaiveTime, Option<i32>),
+    /// A local date and time, and its offset in seconds east of UTC, if it has one
+    DateTime(NaiveDateTime, Option<i32>),
+}
+
+impl Temporal {
+    /// The date or time in a value, or `None` if it isn't one. A `timestamptz` is converted to
+    /// the zone from `--timezone`, and a `timetz` to its own zone.
+    ///
+    /// * `value` - the decoded value
+    /// * `args` - the command line arguments
+    pub fn from_value(value: &ColumnValue, args: &Args) -> Option<Self> {
+        match *value {
+            ColumnValue::Date(date) => Some(Temporal::Date(date)),
+            ColumnValue::Time(time) => Some(Temporal::Time(time, None)),
+            ColumnValue::TimeTz(utc, offset) => Some(Temporal::Time(
+                utc + Duration::seconds(offset as i64),
+                Some(offset),
+            )),
+            ColumnValue::Timestamp(timestamp) => Some(Temporal::DateTime(timestamp, None)),
+            ColumnValue::TimestampTz(utc) => {
+                let (local, offset) = args.time_zone().local(utc);
+                Some(Temporal::DateTime(local, Some(offset)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TimeFormat {
+    /// The format for a column; its own, from the types file, if it has one, or else the one
+    /// from `--time-format`, if there is one.
+    ///
+    /// * `column_conversion` - the column's conversion
+    /// * `args` - the command line arguments
+    pub fn for_column<'a>(
+        column_conversion: &'a Option<ColumnConversion>,
+        args: &'a Args,
+    ) -> Option<&'a TimeFormat> {
+        match column_conversion {
+            Some(ColumnConversion::Format(format)) => Some(format),
+            _ => args.time_format.as_ref(),
+        }
+    }
+
+    /// Whether the values are written as numbers, which go in JSON without quotes.
+    pub fn is_epoch(&self) -> bool {
+        matches!(
+            self,
+            TimeFormat::EpochSeconds
+                | TimeFormat::EpochMilliseconds
+                | TimeFormat::EpochMicroseconds
+        )
+    }
+
+    /// Write a date or time in this format. A time without a date counts from midnight, and a
+    /// date without a time is at midnight, in UTC, for the epoch formats, and for `strftime`,
+    /// times are on 1970-01-01.
+    ///
+    /// * `out` - where to write the value
+    /// * `value` - the value to write
+    /// * `precision` - how many digits of fractional seconds to write, for ISO 8601 and RFC
+    ///   3339; `None` writes 6
+    pub fn write(
+        &self,
+        out: &mut impl Write,
+        value: Temporal,
+        precision: Option<u32>,
+    ) -> fmt::Result {
+        match self {
+            TimeFormat::Iso8601 | TimeFormat::Rfc3339 => {
+                let rfc3339 = *self == TimeFormat::Rfc3339;
+
+                // A date has no fractional seconds to write.
+                let (time, offset, precision) = match value {
+                    Temporal::Date(date) if !rfc3339 => {
+                        return write!(out, "{}", date.format("%F"));
+                    }
+                    Temporal::Date(date) => (date.and_hms(0, 0, 0), None, Some(0)),
+                    Temporal::DateTime(time, offset) => (time, offset, precision),
+                    Temporal::Time(time, offset) => {
+                        write!(out, "{}", time.format("%T"))?;
+                        write_fraction(out, time.nanosecond(), precision)?;
+                        return write_zone(out, offset, rfc3339);
+                    }
+                };
+
+                write!(out, "{}", time.format("%FT%T"))?;
+                write_fraction(out, time.nanosecond(), precision)?;
+                write_zone(out, offset, rfc3339)
+            }
+            TimeFormat::EpochSeconds => {
+                write!(out, "{}", epoch_microseconds(value).div_euclid(1_000_000))
+            }
+            TimeFormat::EpochMilliseconds => {
+                write!(out, "{}", epoch_microseconds(value).div_euclid(1_000))
+            }
+            TimeFormat::EpochMicroseconds => write!(out, "{}", epoch_microseconds(value)),
+            TimeFormat::Strftime(format) => {
+                let (time, offset) = match value {
+                    Temporal::Date(date) => (date.and_hms(0, 0, 0), None),
+                    Temporal::Time(time, offset) => {
+                        (NaiveDate::from_ymd(1970, 1, 1).and_time(time), offset)
+                    }
+                    Temporal::DateTime(time, offset) => (time, offset),
+                };
+
+                let offset = FixedOffset::east(offset.unwrap_or(0));
+                let time = offset.from_local_datetime(&time).unwrap();
+
+                write!(out, "{}", time.format(format))
+            }
+        }
+    }
+}
+
+/// Write a value's offset, as `Z` for UTC, or like `+05:30`. Values without one get nothing,
+/// unless it's RFC 3339, which always has one, so they get `Z`.
+fn write_zone(out: &mut impl Write, offset: Option<i32>, rfc3339: bool) -> fmt::Result {
+    match offset {
+        Some(0) => out.write_char('Z'),
+        Some(offset) => write_offset(out, offset),
+        None if rfc3339 => out.write_char('Z'),
+        None => Ok(()),
+    }
+}
+
+/// The number of microseconds since the Unix epoch, or since midnight for a time without a
+/// date. Values with an offset are converted to UTC first.
+fn epoch_microseconds(value: Temporal) -> i64 {
+    const DAY: i64 = 86_400_000_000;
+
+    let utc_microseconds = |microseconds: i64, offset: Option<i32>| {
+        microseconds - offset.unwrap_or(0) as i64 * 1_000_000
+    };
+
+    match value {
+        Temporal::Date(date) => (date - NaiveDate::from_ymd(1970, 1, 1)).num_days() * DAY,
+        Temporal::Time(time, offset) => {
+            let microseconds = (time - NaiveTime::from_hms(0, 0, 0))
+                .num_microseconds()
+                .unwrap();
+            utc_microseconds(microseconds, offset).rem_euclid(DAY)
+        }
+        Temporal::DateTime(time, offset) => {
+            let epoch = NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0);
+            utc_microseconds((time - epoch).num_microseconds().unwrap(), offset)
+        }
+    }
+}
+
+impl FromStr for TimeFormat {
+    type Err = String;
+
+    /// Parse a format, which is either one of the names `iso8601`, `rfc3339`, `epoch_s`,
+    /// `epoch_ms`, or `epoch_us`, or a `strftime` format, which needs at least one `%`.
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let result = match format.to_lowercase().as_str() {
+            "iso8601" => TimeFormat::Iso8601,
+            "rfc3339" => TimeFormat::Rfc3339,
+            "epoch_s" => TimeFormat::EpochSeconds,
+            "epoch_ms" => TimeFormat::EpochMilliseconds,
+            "epoch_us" => TimeFormat::EpochMicroseconds,
+            _ if !format.contains('%') => {
+                return Err(format!(
+                    "invalid time format: {}; use iso8601, rfc3339, epoch_s, epoch_ms, \
+                     epoch_us, or a strftime format, like %m/%d/%Y",
+                    format
+                ))
+            }
+            _ if StrftimeItems::new(format).any(|item| item == Item::Error) => {
+                return Err(format!("invalid strftime format: {}", format))
+            }
+            _ => TimeFormat::Strftime(format.to_string()),
+        };
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use crate::time_format::{Temporal, TimeFormat};
+
+    fn show(format: &str, value: Temporal, precision: Option<u32>) -> String {
+        let mut output = String::new();
+        format
+            .parse::<TimeFormat>()
+            .unwrap()
+            .write(&mut output, value, precision)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn test_parse_time_format() {
+        assert_eq!("ISO8601".parse(), Ok(TimeFormat::Iso8601));
+        assert_eq!("epoch_ms".parse(), Ok(TimeFormat::EpochMilliseconds));
+        assert_eq!(
+            "%m/%d/%Y".parse(),
+            Ok(TimeFormat::Strftime("%m/%d/%Y".to_string()))
+        );
+
+        assert!("epoch".parse::<TimeFormat>().is_err());
+        assert!("%Q".parse::<TimeFormat>().is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        let timestamp = NaiveDate::from_ymd(2023, 1, 2).and_hms_micro(3, 4, 5, 250_000);
+        let date = Temporal::Date(NaiveDate::from_ymd(2023, 1, 2));
+        let time = Temporal::Time(NaiveTime::from_hms(10, 4, 5), Some(-5 * 3600));
+
+        let cases = vec![
+            (
+                "iso8601",
+                Temporal::DateTime(timestamp, None),
+                "2023-01-02T03:04:05.250000",
+            ),
+            (
+                "iso8601",
+                Temporal::DateTime(timestamp, Some(0)),
+                "2023-01-02T03:04:05.250000Z",
+            ),
+            ("iso8601", date, "2023-01-02"),
+            ("iso8601", time, "10:04:05.000000-05:00"),
+            (
+                "rfc3339",
+                Temporal::DateTime(timestamp, None),
+                "2023-01-02T03:04:05.250000Z",
+            ),
+            (
+                "rfc3339",
+                Temporal::DateTime(timestamp, Some(19800)),
+                "2023-01-02T03:04:05.250000+05:30",
+            ),
+            ("rfc3339", date, "2023-01-02T00:00:00Z"),
+            ("epoch_s", Temporal::DateTime(timestamp, None), "1672628645"),
+            (
+                "epoch_ms",
+                Temporal::DateTime(timestamp, None),
+                "1672628645250",
+            ),
+            (
+                "epoch_us",
+                Temporal::DateTime(timestamp, None),
+                "1672628645250000",
+            ),
+            (
+                "epoch_s",
+                Temporal::DateTime(timestamp, Some(3600)),
+                "1672625045",
+            ),
+            ("epoch_s", date, "1672617600"),
+            ("epoch_s", time, "54245"),
+            (
+                "epoch_ms",
+                Temporal::DateTime(
+                    NaiveDate::from_ymd(1969, 12, 31).and_hms_milli(23, 59, 59, 500),
+                    None,
+                ),
+                "-500",
+            ),
+            (
+                "epoch_s",
+                Temporal::Date(NaiveDate::from_ymd(1969, 12, 31)),
+                "-86400",
+            ),
+        ];
+
+        for (format, value, expected) in cases {
+            assert_eq!(
+                show(format, value, None),
+                expected,
+                "{} {:?}",
+                format,
+                value
+            );
+        }
+
+        assert_eq!(
+            show("iso8601", Temporal::DateTime(timestamp, None), Some(6)),
+            "2023-01-02T03:04:05.250000"
+        );
+    }
+
+    #[test]
+    fn test_strftime() {
+        let timestamp = NaiveDate::from_ymd(2023, 1, 2).and_hms(3, 4, 5);
+
+        assert_eq!(
+            show(
+                "%m/%d/%Y",
+                Temporal::Date(NaiveDate::from_ymd(2023, 1, 2)),
+                None
+            ),
+            "01/02/2023"
+        );
+        assert_eq!(
+            show(
+                "%d.%m.%Y %H:%M %z",
+                Temporal::DateTime(timestamp, Some(3600)),
+                None
+            ),
+            "02.01.2023 03:04 +0100"
+        );
+        assert_eq!(
+            show(
+                "%I:%M %p",
+                Temporal::Time(NaiveTime::from_hms(15, 12, 0), None),
+                None
+            ),
+            "03:12 PM"
+        );
+    }
+}
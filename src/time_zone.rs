@@ -0,0 +1,146 @@
+use std::str::FromStr;
+
+use chrono::{FixedOffset, NaiveDateTime, Offset, TimeZone as _};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// The time zone `TimestampTz` values are shown in, from `--timezone` or `--tz-offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeZone {
+    /// The same offset from UTC all year round
+    Fixed(FixedOffset),
+    /// A zone from the tz database, like `America/New_York`, whose offset depends on the date
+    Named(Tz),
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        TimeZone::Fixed(FixedOffset::east(0))
+    }
+
+    /// A fixed offset of a whole number of hours, as given to `--tz-offset`.
+    pub fn from_hours(hours: i8) -> Self {
+        TimeZone::Fixed(FixedOffset::east(hours as i32 * 3600))
+    }
+
+    /// Convert the instant `utc` to the local time in this zone. Returns the local time, and
+    /// the zone's offset from UTC, in seconds east. For a named zone, the offset is the one in
+    /// effect at that instant, so daylight saving time is taken into account.
+    ///
+    /// * `utc` - the instant, in UTC
+    pub fn local(&self, utc: NaiveDateTime) -> (NaiveDateTime, i32) {
+        let offset = match self {
+            TimeZone::Fixed(offset) => *offset,
+            TimeZone::Named(zone) => zone.offset_from_utc_datetime(&utc).fix(),
+        };
+
+        (utc + offset, offset.local_minus_utc())
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+
+    /// Parse a time zone, which is either a name from the tz database, like `Asia/Kolkata`, or
+    /// an offset from UTC, like `+05:30`, `-0800`, `+09`, or `Z`.
+    fn from_str(zone: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref OFFSET_REGEX: Regex = Regex::new(r"^([+-])(\d{2}):?(\d{2})?$").unwrap();
+        }
+
+        if zone == "Z" || zone.eq_ignore_ascii_case("utc") {
+            return Ok(TimeZone::utc());
+        }
+
+        if let Some(captures) = OFFSET_REGEX.captures(zone) {
+            let hours: i32 = captures[2].parse().unwrap();
+            let minutes: i32 = captures.get(3).map_or(0, |m| m.as_str().parse().unwrap());
+
+            if hours > 23 || minutes > 59 {
+                return Err(format!("invalid offset: {}", zone));
+            }
+
+            let seconds = (hours * 60 + minutes) * 60;
+            let sign = if &captures[1] == "-" { -1 } else { 1 };
+
+            return Ok(TimeZone::Fixed(FixedOffset::east(sign * seconds)));
+        }
+
+        match zone.parse::<Tz>() {
+            Ok(zone) => Ok(TimeZone::Named(zone)),
+            Err(_) => Err(format!(
+                "unknown time zone: {}; use a name like America/New_York, or an offset like +05:30",
+                zone
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+
+    use crate::time_zone::TimeZone;
+
+    fn show(zone: &str, utc: NaiveDateTime) -> String {
+        let (local, offset) = zone.parse::<TimeZone>().unwrap().local(utc);
+        format!("{} {}", local, offset)
+    }
+
+    #[test]
+    fn test_parse_time_zone() {
+        assert_eq!("UTC".parse::<TimeZone>(), Ok(TimeZone::utc()));
+        assert_eq!("Z".parse::<TimeZone>(), Ok(TimeZone::utc()));
+        assert_eq!(
+            "+05:30".parse::<TimeZone>(),
+            Ok(TimeZone::Fixed(FixedOffset::east(19800)))
+        );
+        assert_eq!(
+            "-0800".parse::<TimeZone>(),
+            Ok(TimeZone::Fixed(FixedOffset::west(28800)))
+        );
+        assert_eq!("+09".parse::<TimeZone>(), Ok(TimeZone::from_hours(9)));
+        assert_eq!(
+            "Asia/Kathmandu".parse::<TimeZone>(),
+            Ok(TimeZone::Named(chrono_tz::Asia::Kathmandu))
+        );
+
+        assert!("+24:00".parse::<TimeZone>().is_err());
+        assert!("+05:60".parse::<TimeZone>().is_err());
+        assert!("5".parse::<TimeZone>().is_err());
+        assert!("Mars/Olympus_Mons".parse::<TimeZone>().is_err());
+    }
+
+    #[test]
+    fn test_local_time() {
+        let utc = NaiveDate::from_ymd(1999, 1, 8).and_hms(12, 4, 37);
+
+        assert_eq!(show("UTC", utc), "1999-01-08 12:04:37 0");
+        assert_eq!(show("Asia/Kolkata", utc), "1999-01-08 17:34:37 19800");
+        assert_eq!(show("Asia/Kathmandu", utc), "1999-01-08 17:49:37 20700");
+        assert_eq!(show("-03:30", utc), "1999-01-08 08:34:37 -12600");
+    }
+
+    #[test]
+    fn test_daylight_saving_time() {
+        // Daylight saving time started in New York at 2am on 14 March 2021, which was 7am UTC.
+        let before = NaiveDate::from_ymd(2021, 3, 14).and_hms(6, 59, 59);
+        let after = NaiveDate::from_ymd(2021, 3, 14).and_hms(7, 0, 0);
+
+        assert_eq!(
+            show("America/New_York", before),
+            "2021-03-14 01:59:59 -18000"
+        );
+        assert_eq!(
+            show("America/New_York", after),
+            "2021-03-14 03:00:00 -14400"
+        );
+
+        let summer = NaiveDate::from_ymd(2021, 7, 1).and_hms(12, 0, 0);
+        assert_eq!(
+            show("America/New_York", summer),
+            "2021-07-01 08:00:00 -14400"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::fs::File;
+use std::io::{stdout, BufReader, Read, Write};
+
+use anyhow::bail;
+
+use crate::args::ValidateArgs;
+use crate::column_definitions::ColumnDefinitions;
+use crate::file_signature::{FileSignature, FILE_SIGNATURE_LENGTH};
+use crate::read_u32;
+use crate::vertica_native_file::{bitfield_length, Row};
+
+/// Something wrong with a native file, found while validating it.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// The row with the problem, counting from 1, or `None` if the problem isn't in a row
+    pub row: Option<usize>,
+    /// The offset, in bytes from the start of the file, of the row or other part with the problem
+    pub offset: u64,
+    pub message: String,
+}
+
+/// The result of validating a native file.
+#[derive(Debug)]
+pub struct Report {
+    /// The number of rows that were read, including any with problems
+    pub rows: usize,
+    pub problems: Vec<Problem>,
+}
+
+/// Run the `validate` subcommand. The report goes to `stdout`, and if there are any problems,
+/// an error is returned, so the program exits with a non-zero status.
+///
+/// * `args` - the subcommand's arguments
+pub fn validate_file(args: &ValidateArgs) -> anyhow::Result<()> {
+    let mut reader = match File::open(&args.input) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => bail!("opening input file [{}]: {}", args.input, e),
+    };
+
+    let report = validate(&mut reader)?;
+
+    let stdout = stdout();
+    let mut writer = stdout.lock();
+
+    for problem in &report.problems {
+        match problem.row {
+            Some(row) => writeln!(
+                writer,
+                "row {}, offset {}: {}",
+                row, problem.offset, problem.message
+            )?,
+            None => writeln!(writer, "offset {}: {}", problem.offset, problem.message)?,
+        }
+    }
+
+    if report.problems.is_empty() {
+        writeln!(writer, "{}: valid, {} rows", args.input, report.rows)?;
+        Ok(())
+    } else {
+        writeln!(
+            writer,
+            "{}: {} problem{}, {} rows",
+            args.input,
+            report.problems.len(),
+            if report.problems.len() == 1 { "" } else { "s" },
+            report.rows
+        )?;
+        bail!("{} is not valid", args.input)
+    }
+}
+
+/// Check a whole native file, from the signature to the end. Problems with the file are
+/// collected in the report, rather than being errors; only a failure to read at all is an error.
+///
+/// Once the header has been read, every row is checked, as long as the rows can still be
+/// found. A row whose columns don't match its length is reported, but since the length says
+/// where the next row starts, checking carries on from there. A row that runs past the end of
+/// the file ends the checking.
+///
+/// * `reader` - the file, positioned at its start
+pub fn validate(reader: &mut impl Read) -> anyhow::Result<Report> {
+    let mut report = Report {
+        rows: 0,
+        problems: vec![],
+    };
+
+    match FileSignature::from_reader_unchecked(reader) {
+        Ok(signature) if signature.is_valid() => {}
+        Ok(_) => report.file_problem(0, "signature is invalid".to_string()),
+        Err(_) => {
+            report.file_problem(0, "file is too short to have a signature".to_string());
+            return Ok(report);
+        }
+    }
+
+    let definitions = match ColumnDefinitions::from_reader(reader) {
+        Ok(definitions) => definitions,
+        Err(_) => {
+            report.file_problem(
+                FILE_SIGNATURE_LENGTH as u64,
+                "header is truncated".to_string(),
+            );
+            return Ok(report);
+        }
+    };
+
+    // The header length covers the version, the filler, the column count, and the widths.
+    let column_count = definitions.column_widths.len();
+    let expected_header_length = 2 + 1 + 2 + 4 * column_count as u32;
+
+    if definitions.header_length != expected_header_length {
+        report.file_problem(
+            FILE_SIGNATURE_LENGTH as u64,
+            format!(
+                "header length is {}, but {} columns need a header length of {}",
+                definitions.header_length, column_count, expected_header_length
+            ),
+        );
+    }
+
+    let bitfield_length = bitfield_length(column_count);
+    let mut offset = (FILE_SIGNATURE_LENGTH + definitions.length()) as u64;
+    let mut buffer: Vec<u8> = vec![];
+    let mut columns = Vec::with_capacity(column_count);
+
+    loop {
+        // Whatever follows a row has to be either another row, or nothing at all.
+        buffer.clear();
+        reader.take(4).read_to_end(&mut buffer)?;
+
+        match buffer.len() {
+            0 => break,
+            4 => {}
+            left_over => {
+                report.file_problem(
+                    offset,
+                    format!("{} bytes left over at the end of the file", left_over),
+                );
+                break;
+            }
+        }
+
+        let row_length = read_u32(&mut &buffer[..])?;
+        let length = bitfield_length as u64 + row_length as u64;
+
+        report.rows += 1;
+        let row = report.rows;
+
+        buffer.clear();
+        reader.take(length).read_to_end(&mut buffer)?;
+
+        if (buffer.len() as u64) < length {
+            report.row_problem(
+                row,
+                offset,
+                format!(
+                    "row length is {}, but the row runs past the end of the file",
+                    row_length
+                ),
+            );
+            break;
+        }
+
+        let (bitfield, data) = buffer.split_at(bitfield_length);
+
+        match Row::locate_columns(&definitions.column_widths, bitfield, data, &mut columns) {
+            Ok(used) if used != data.len() => report.row_problem(
+                row,
+                offset,
+                format!(
+                    "row length is {}, but its columns only take {} bytes",
+                    row_length, used
+                ),
+            ),
+            Ok(_) => {}
+            Err(e) => report.row_problem(
+                row,
+                offset,
+                format!("row length is {}, but {}", row_length, e),
+            ),
+        }
+
+        offset += 4 + length;
+    }
+
+    Ok(report)
+}
+
+impl Report {
+    fn file_problem(&mut self, offset: u64, message: String) {
+        self.problems.push(Problem {
+            row: None,
+            offset,
+            message,
+        });
+    }
+
+    fn row_problem(&mut self, row: usize, offset: u64, message: String) {
+        self.problems.push(Problem {
+            row: Some(row),
+            offset,
+            message,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::fs;
+
+    use crate::validate::{validate, Problem};
+
+    /// The offset of the first row in the test files
+    const FIRST_ROW: usize = 76;
+
+    fn find_problems(bytes: &[u8]) -> Vec<Problem> {
+        validate(&mut &bytes[..]).unwrap().problems
+    }
+
+    fn set_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_valid_files() {
+        for file_name in &[
+            "data/all-types.bin",
+            "data/all-types-ten-rows.bin",
+            "data/all-types-with-nulls.bin",
+        ] {
+            let bytes = fs::read(file_name).unwrap();
+            let report = validate(&mut &bytes[..]).unwrap();
+
+            assert_eq!(report.problems, vec![], "{}", file_name);
+        }
+
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+        assert_eq!(validate(&mut &bytes[..]).unwrap().rows, 10);
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let bytes = fs::read("data/all-types-with-bad-signature.bin").unwrap();
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].message, "signature is invalid");
+
+        let mut bytes = fs::read("data/all-types.bin").unwrap();
+        set_u32(&mut bytes, 11, 60);
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].row, None);
+        assert_eq!(problems[0].offset, 11);
+        assert_eq!(
+            problems[0].message,
+            "header length is 60, but 14 columns need a header length of 61"
+        );
+
+        assert_eq!(
+            find_problems(&bytes[..40])[0].message,
+            "header is truncated"
+        );
+    }
+
+    #[test]
+    fn test_bad_row_lengths() {
+        let good = fs::read("data/all-types-ten-rows.bin").unwrap();
+        let row_length = u32::from_le_bytes(good[FIRST_ROW..FIRST_ROW + 4].try_into().unwrap());
+        let second_row = FIRST_ROW + 4 + 2 + row_length as usize;
+
+        // Too long: the first row's columns don't fill it, and the rest of the rows are out of
+        // step, so they're wrong too.
+        let mut bytes = good.clone();
+        set_u32(&mut bytes, FIRST_ROW, row_length + 1);
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems[0].row, Some(1));
+        assert_eq!(problems[0].offset, FIRST_ROW as u64);
+        assert_eq!(
+            problems[0].message,
+            format!(
+                "row length is {}, but its columns only take {} bytes",
+                row_length + 1,
+                row_length
+            )
+        );
+
+        // Too short, in the second row
+        let mut bytes = good.clone();
+        set_u32(&mut bytes, second_row, row_length - 4);
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems[0].row, Some(2));
+        assert_eq!(problems[0].offset, second_row as u64);
+        assert!(problems[0].message.contains("runs past the end of the row"));
+
+        // Past the end of the file
+        let mut bytes = good.clone();
+        set_u32(&mut bytes, second_row, 100_000);
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].message,
+            "row length is 100000, but the row runs past the end of the file"
+        );
+    }
+
+    #[test]
+    fn test_left_over_bytes() {
+        let mut bytes = fs::read("data/all-types.bin").unwrap();
+        let end = bytes.len() as u64;
+        bytes.extend_from_slice(&[0, 0]);
+
+        let problems = find_problems(&bytes);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].offset, end);
+        assert_eq!(
+            problems[0].message,
+            "2 bytes left over at the end of the file"
+        );
+    }
+}
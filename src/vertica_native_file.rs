@@ -1,18 +1,241 @@
-use std::collections::HashMap;
-use std::io::Read;
+use std::convert::TryInto;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
 
-use serde_json::{Number, Value};
+use anyhow::{anyhow, bail};
+use csv::{QuoteStyle, Writer, WriterBuilder};
 
+use crate::args::JsonNonFinite;
 use crate::column_definitions::ColumnDefinitions;
 use crate::column_type::ColumnType;
 use crate::column_types::ColumnTypes;
-use crate::file_signature::FileSignature;
-use crate::{read_u32, read_variable, Args};
+use crate::column_value::ColumnValue;
+use crate::file_signature::{FileSignature, FILE_SIGNATURE_LENGTH};
+use crate::index::RowIndex;
+use crate::time_format::TimeFormat;
+use crate::Args;
+
+/// Something that can be both read and seeked, like a buffered file.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// The reader behind a stream. If it can seek, the file can jump straight to a row; otherwise,
+/// it can only move forwards.
+enum Reader<'a> {
+    Plain(&'a mut dyn Read),
+    Seekable(&'a mut dyn ReadSeek),
+}
+
+impl Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Reader::Plain(reader) => reader.read(buf),
+            Reader::Seekable(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Read the length at the start of a row, or `None` if the file ends cleanly before it. A length
+/// of 0 is a row with no data, because all its columns are null, not the end of the file.
+fn read_row_length(reader: &mut impl Read) -> anyhow::Result<Option<u32>> {
+    let mut bytes = [0u8; 4];
+    let mut filled = 0;
+
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    match filled {
+        0 => Ok(None),
+        4 => Ok(Some(u32::from_le_bytes(bytes))),
+        left_over => bail!("{} bytes left over at the end of the file", left_over),
+    }
+}
+
+/// The longest a row's length can be when the file's length isn't known, so a corrupt length
+/// can't make a stream allocate gigabytes. Vertica's own limit is 32MB a row.
+const MAX_ROW_LENGTH: u64 = 32 * 1024 * 1024;
+
+/// Where the rows of a native file come from.
+enum Input<'a> {
+    /// A stream, read one row at a time into `buffer`. The buffer is reused for every row, so
+    /// once it has grown to the size of the longest row, reading rows doesn't allocate.
+    /// `length` is the length of the file, if the reader can seek to find it.
+    Stream {
+        reader: Reader<'a>,
+        buffer: Vec<u8>,
+        length: Option<u64>,
+    },
+    /// The whole file, already in memory (usually because it's memory-mapped). Rows are sliced
+    /// straight out of it, without copying. `position` is the offset of the next row, and `row`
+    /// is the range of the row that was fetched last.
+    Bytes {
+        bytes: &'a [u8],
+        position: usize,
+        row: Range<usize>,
+    },
+}
+
+impl<'a> Input<'a> {
+    /// Fetch the next row; either the whole thing, or just its null bitfield, skipping the rest.
+    /// Returns the length of the row, including the bitfield, but not the `u32` that gives the
+    /// length, or `None` if there are no more rows.
+    ///
+    /// * `offset` - the offset of the row, in bytes from the start of the file
+    /// * `bitfield_length` - the length of the null bitfield at the start of each row
+    /// * `whole_row` - whether to fetch the whole row, or just its bitfield
+    fn fetch(
+        &mut self,
+        offset: u64,
+        bitfield_length: usize,
+        whole_row: bool,
+    ) -> anyhow::Result<Option<usize>> {
+        match self {
+            Input::Stream {
+                reader,
+                buffer,
+                length: file_length,
+            } => {
+                // First, read a `u32` which gives the length of the row, not including the length,
+                // or the bitfield indicating null values.
+                let row_length = match read_row_length(reader)? {
+                    Some(length) => length,
+                    None => return Ok(None),
+                };
+
+                // The length hasn't been checked yet, so don't trust it with an allocation.
+                match file_length {
+                    Some(file_length) => {
+                        let left = file_length.saturating_sub(offset + 4);
+
+                        if bitfield_length as u64 + row_length as u64 > left {
+                            bail!(
+                                "row length is {}, but there are only {} bytes left in the file",
+                                row_length,
+                                left
+                            );
+                        }
+                    }
+                    None if row_length as u64 > MAX_ROW_LENGTH => bail!(
+                        "row length is {}, which is more than any row can be",
+                        row_length
+                    ),
+                    None => {}
+                }
+
+                let length = bitfield_length + row_length as usize;
+
+                // Then read the bitfield and, if it's wanted, the row's data, in one go.
+                buffer.resize(if whole_row { length } else { bitfield_length }, 0);
+
+                if reader.read_exact(buffer).is_err() {
+                    bail!("row runs past the end of the file");
+                }
+
+                // The rest of the row is stepped over. If the reader can seek, it jumps; the row's
+                // length has been checked against the file's already, so the jump can't go past
+                // the end of it.
+                if !whole_row {
+                    let rest = row_length as u64;
+
+                    match reader {
+                        Reader::Seekable(reader) => {
+                            reader.seek(SeekFrom::Current(rest as i64))?;
+                        }
+                        Reader::Plain(_) => {
+                            let skipped = io::copy(&mut reader.take(rest), &mut io::sink())?;
+
+                            if skipped < rest {
+                                bail!("row runs past the end of the file");
+                            }
+                        }
+                    }
+                }
+
+                Ok(Some(length))
+            }
+            Input::Bytes {
+                bytes,
+                position,
+                row,
+            } => {
+                let mut rest = &bytes[*position..];
+
+                let row_length = match read_row_length(&mut rest)? {
+                    Some(length) => length,
+                    None => return Ok(None),
+                };
+
+                let length = bitfield_length + row_length as usize;
+
+                if rest.len() < length {
+                    bail!("row runs past the end of the file");
+                }
+
+                let start = *position + 4;
+                *row = start..start + if whole_row { length } else { bitfield_length };
+                *position = start + length;
+
+                Ok(Some(length))
+            }
+        }
+    }
+
+    /// The bytes fetched by the last call to `fetch`.
+    fn fetched(&self) -> &[u8] {
+        match self {
+            Input::Stream { buffer, .. } => buffer,
+            Input::Bytes { bytes, row, .. } => &bytes[row.clone()],
+        }
+    }
+
+    /// Whether `seek` can be used; only a stream that can't seek can't.
+    fn can_seek(&self) -> bool {
+        !matches!(
+            self,
+            Input::Stream {
+                reader: Reader::Plain(_),
+                ..
+            }
+        )
+    }
+
+    /// Move to `offset`, in bytes from the start of the file, so the next row is fetched from
+    /// there.
+    fn seek(&mut self, offset: u64) -> anyhow::Result<()> {
+        match self {
+            Input::Stream {
+                reader: Reader::Seekable(reader),
+                ..
+            } => {
+                reader.seek(SeekFrom::Start(offset))?;
+            }
+            Input::Stream { .. } => bail!("can't seek in a stream"),
+            Input::Bytes {
+                bytes, position, ..
+            } => {
+                if offset > bytes.len() as u64 {
+                    bail!("offset {} is past the end of the file", offset);
+                }
+
+                *position = offset as usize;
+            }
+        }
+
+        Ok(())
+    }
+}
 
 /// The [Vertica native binary](https://www.vertica.com/docs/9.3.x/HTML/Content/Authoring/AdministratorsGuide/BinaryFilesAppendix/CreatingNativeBinaryFormatFiles.htm)
 /// is a compact, structured, binary file format for copy large amounts of data into the Vertica
 /// database. The file structure can be found at the above link. This struct contains all the
-/// metadata read from the header, and provides an iterator to walk through the individual rows
+/// metadata read from the header, and provides a way to walk through the individual rows
 /// of data.
 ///
 /// A diagram of the header layout can be found [here](https://www.vertica.com/docs/9.3.x/HTML/Content/Authoring/AdministratorsGuide/BinaryFilesAppendix/FileSignature.htm).
@@ -22,189 +245,549 @@ pub struct VerticaNativeFile<'a> {
     _signature: FileSignature,
     /// The definitions for all the columns
     pub definitions: ColumnDefinitions,
+    /// Whether a row whose columns don't match its length is skipped, rather than ending the
+    /// iteration
+    pub skip_bad_rows: bool,
+    /// The number of the last row to read, counting from 1; the file is treated as ending
+    /// after it
+    pub last_row: usize,
+    /// An index of where some of the rows start, which `seek_to_row` uses, if it's there
+    pub index: Option<RowIndex>,
     /// The input source of the file
-    file: &'a mut dyn Read,
+    input: Input<'a>,
+    /// Where each column of the current row lives in the row's data, or `None` if it's null.
+    /// Like the stream buffer, this is reused from row to row.
+    columns: Vec<Option<Range<usize>>>,
+    /// The number of rows fetched so far, including any that were skipped
+    row_number: usize,
+    /// The offset of the row fetched last, in bytes from the start of the file
+    row_offset: u64,
+    /// The offset of the next row
+    next_offset: u64,
 }
 
 impl<'a> VerticaNativeFile<'a> {
     /// Create the struct from the `reader`
     pub fn from_reader(reader: &'a mut impl Read) -> anyhow::Result<Self> {
-        let signature = FileSignature::from_reader(reader)?;
-        let definitions = ColumnDefinitions::from_reader(reader)?;
+        Self::from_stream(Reader::Plain(reader))
+    }
+
+    /// Create the struct from a `reader` that can seek, such as a file, so `seek_to_row` can
+    /// jump straight to a row, or back to an earlier one. It has to be positioned at the start
+    /// of the file.
+    pub fn from_seekable_reader(reader: &'a mut impl ReadSeek) -> anyhow::Result<Self> {
+        Self::from_stream(Reader::Seekable(reader))
+    }
+
+    fn from_stream(mut reader: Reader<'a>) -> anyhow::Result<Self> {
+        let length = match &mut reader {
+            Reader::Seekable(reader) => {
+                let length = reader.seek(SeekFrom::End(0))?;
+                reader.seek(SeekFrom::Start(0))?;
+                Some(length)
+            }
+            Reader::Plain(_) => None,
+        };
+        let signature = FileSignature::from_reader(&mut reader)?;
+        let definitions = ColumnDefinitions::from_reader(&mut reader)?;
+        let header_length = FILE_SIGNATURE_LENGTH + definitions.length();
 
         Ok(VerticaNativeFile {
             _signature: signature,
+            columns: Vec::with_capacity(definitions.column_widths.len()),
             definitions,
-            file: reader,
+            skip_bad_rows: false,
+            last_row: usize::MAX,
+            index: None,
+            input: Input::Stream {
+                reader,
+                buffer: vec![],
+                length,
+            },
+            row_number: 0,
+            row_offset: 0,
+            next_offset: header_length as u64,
         })
     }
-}
-
-impl<'a> Iterator for VerticaNativeFile<'a> {
-    type Item = Row;
 
-    /// Iterate through all the rows of the native file, returning them for further processing.
-    fn next(&mut self) -> Option<Self::Item> {
-        // First, read a `u32` which gives the length of the row, not including the length,
-        // or the bitfield indicating null values.
-        let row_length = match read_u32(&mut self.file) {
-            Ok(length) => length,
-            Err(_) => return None,
-        };
+    /// Create the struct from a file that is entirely in memory, such as a memory-mapped file.
+    pub fn from_bytes(bytes: &'a [u8]) -> anyhow::Result<Self> {
+        let mut header = bytes;
+        let signature = FileSignature::from_reader(&mut header)?;
+        let definitions = ColumnDefinitions::from_reader(&mut header)?;
+        let header_length = bytes.len() - header.len();
 
-        if row_length <= 0 {
-            return None;
-        }
+        Ok(VerticaNativeFile {
+            _signature: signature,
+            columns: Vec::with_capacity(definitions.column_widths.len()),
+            definitions,
+            skip_bad_rows: false,
+            last_row: usize::MAX,
+            index: None,
+            input: Input::Bytes {
+                bytes,
+                position: header_length,
+                row: 0..0,
+            },
+            row_number: 0,
+            row_offset: 0,
+            next_offset: header_length as u64,
+        })
+    }
 
-        match Row::from_reader(&mut self.file, &self.definitions.column_widths) {
-            Ok(row) => Some(row),
+    /// Return the next row of the file, or `None` once there are no more. A row that can't be
+    /// read is reported on `stderr`, and also ends the iteration, unless `skip_bad_rows` is set
+    /// and the row can be skipped.
+    ///
+    /// The returned `Row` borrows from the file, so it has to be dropped before the next call.
+    pub fn next_row(&mut self) -> Option<Row<'_>> {
+        match self.read_row() {
+            Ok(row) => row,
             Err(e) => {
                 eprintln!("reading data: {}", e);
                 None
             }
         }
     }
+
+    /// Fetch the next row, and return its null bitfield and its data, or `None` once there are no
+    /// more rows. Where its columns are isn't worked out, so, unlike `next_row`, a row whose
+    /// columns don't fit its length is still returned, and can be looked at.
+    pub fn next_raw_row(&mut self) -> anyhow::Result<Option<(&[u8], &[u8])>> {
+        let bitfield_length = bitfield_length(self.definitions.column_widths.len());
+
+        match self.fetch_row(true)? {
+            Some(_) => Ok(Some(self.input.fetched().split_at(bitfield_length))),
+            None => Ok(None),
+        }
+    }
+
+    /// Step over the next row, using only its length, and return the row's null bitfield, or
+    /// `None` once there are no more rows. Only the bitfield is read; the row's data is skipped,
+    /// without looking at any of its columns.
+    pub fn next_bitfield(&mut self) -> anyhow::Result<Option<&[u8]>> {
+        match self.fetch_row(false)? {
+            Some(_) => Ok(Some(self.input.fetched())),
+            None => Ok(None),
+        }
+    }
+
+    /// Step over the next `count` rows, using only their lengths, without reading any of their
+    /// columns. Return the number of rows skipped, which is fewer than `count` if the file ends
+    /// first.
+    pub fn skip_rows(&mut self, count: usize) -> anyhow::Result<usize> {
+        let mut skipped: usize = 0;
+
+        while skipped < count && self.fetch_row(false)?.is_some() {
+            skipped += 1;
+        }
+
+        Ok(skipped)
+    }
+
+    /// Get ready to read row `row`, counting from 1, so it's the next one returned. If there's
+    /// an index, and the input can seek, this jumps to the closest indexed row before it, and
+    /// steps over the rest using their lengths; otherwise, every row in between is stepped over.
+    /// Going back to an earlier row needs an input that can seek.
+    pub fn seek_to_row(&mut self, row: usize) -> anyhow::Result<()> {
+        let before = row.saturating_sub(1);
+
+        if self.input.can_seek() {
+            let closest = self.index.as_ref().and_then(|index| index.locate(row));
+
+            if let Some((indexed_before, offset)) = closest {
+                if indexed_before > self.row_number || before < self.row_number {
+                    self.jump(indexed_before, offset)?;
+                }
+            }
+
+            if before < self.row_number {
+                self.jump(
+                    0,
+                    (FILE_SIGNATURE_LENGTH + self.definitions.length()) as u64,
+                )?;
+            }
+        } else if before < self.row_number {
+            bail!(
+                "can't go back to row {} after row {}, because the input can't seek",
+                row,
+                self.row_number
+            );
+        }
+
+        self.skip_rows(before - self.row_number)?;
+
+        Ok(())
+    }
+
+    /// Move the input to `offset`, which is where the row after row `row_number` starts.
+    fn jump(&mut self, row_number: usize, offset: u64) -> anyhow::Result<()> {
+        self.input.seek(offset)?;
+        self.row_number = row_number;
+        self.next_offset = offset;
+
+        Ok(())
+    }
+
+    /// The number of rows fetched so far, which is also the number of the row fetched last,
+    /// counting from 1.
+    pub fn row_number(&self) -> usize {
+        self.row_number
+    }
+
+    /// The offset of the row fetched last, in bytes from the start of the file.
+    pub fn row_offset(&self) -> u64 {
+        self.row_offset
+    }
+
+    /// Fetch the next row from the input, keeping track of its number and offset, for reporting
+    /// problems.
+    fn fetch_row(&mut self, whole_row: bool) -> anyhow::Result<Option<usize>> {
+        if self.row_number >= self.last_row {
+            return Ok(None);
+        }
+
+        let bitfield_length = bitfield_length(self.definitions.column_widths.len());
+
+        match self
+            .input
+            .fetch(self.next_offset, bitfield_length, whole_row)
+        {
+            Ok(Some(length)) => {
+                self.row_number += 1;
+                self.row_offset = self.next_offset;
+                self.next_offset += 4 + length as u64;
+                Ok(Some(length))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => bail!(
+                "row {} at offset {}: {}",
+                self.row_number + 1,
+                self.next_offset,
+                e
+            ),
+        }
+    }
+
+    /// Like `next_row`, but a row that can't be read is returned as an error, instead of being
+    /// reported on `stderr`, so the caller can tell a damaged file from one that has ended.
+    pub fn read_row(&mut self) -> anyhow::Result<Option<Row<'_>>> {
+        let bitfield_length = bitfield_length(self.definitions.column_widths.len());
+
+        loop {
+            if self.fetch_row(true)?.is_none() {
+                return Ok(None);
+            }
+
+            let (bitfield, data) = self.input.fetched().split_at(bitfield_length);
+
+            // The row's length is the one thing we know the row's columns have to fit in. If they
+            // run past the end of it, or don't fill it, something in the row is corrupt. But the
+            // next row still starts where the length says, so the row can be skipped.
+            let problem = match Row::locate_columns(
+                &self.definitions.column_widths,
+                bitfield,
+                data,
+                &mut self.columns,
+            ) {
+                Ok(used) if used == data.len() => None,
+                Ok(used) => Some(anyhow!(
+                    "row length is {}, but its columns only take {} bytes",
+                    data.len(),
+                    used
+                )),
+                Err(e) => Some(anyhow!("row length is {}, but {}", data.len(), e)),
+            };
+
+            match problem {
+                None => break,
+                Some(e) if self.skip_bad_rows => eprintln!(
+                    "reading data: row {} at offset {}: {}; skipping to the next row",
+                    self.row_number, self.row_offset, e
+                ),
+                Some(e) => bail!(
+                    "row {} at offset {}: {}",
+                    self.row_number,
+                    self.row_offset,
+                    e
+                ),
+            }
+        }
+
+        let (bitfield, data) = self.input.fetched().split_at(bitfield_length);
+
+        Ok(Some(Row {
+            bitfield,
+            data,
+            columns: &self.columns,
+        }))
+    }
+}
+
+/// The number of bytes in the null bitfield at the start of each row. There is one bit per
+/// column, rounded up to a whole byte.
+pub fn bitfield_length(number_of_columns: usize) -> usize {
+    number_of_columns.div_ceil(8)
+}
+
+/// Read a little-endian `u32` from `data`, starting at `offset`, if there are enough bytes.
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+
+    Some(u32::from_le_bytes(bytes.try_into().unwrap()))
 }
 
 #[derive(Debug)]
-#[allow(unused)]
-/// A struct containing a single row of data from the native file.
+/// A single row of data from the native file. It doesn't own any of its data; the column values
+/// are all slices of the buffer the row was read into.
 ///
 /// The layout of the the bytes for each row can be found [here](https://www.vertica.com/docs/9.3.x/HTML/Content/Authoring/AdministratorsGuide/BinaryFilesAppendix/RowData.htm).
 ///
-pub struct Row {
-    pub null_values: Vec<bool>,
-    pub data: Vec<Option<Vec<u8>>>,
+pub struct Row<'a> {
+    bitfield: &'a [u8],
+    data: &'a [u8],
+    columns: &'a [Option<Range<usize>>],
 }
 
-impl Row {
-    /// Create a `Row` from the binary file.
-    fn from_reader(reader: &mut impl Read, column_widths: &Vec<u32>) -> anyhow::Result<Self> {
-        let mut data: Vec<Option<Vec<u8>>> = vec![];
+impl<'a> Row<'a> {
+    /// Work out where each column lives in the row's `data`, and store the ranges in `columns`.
+    /// Nothing is copied or decoded here; each column is just stepped over, using either its
+    /// fixed width or its length prefix. Decoding only happens for the columns that are output.
+    ///
+    /// * `column_widths` - the widths from the file header
+    /// * `bitfield` - the null bitfield that comes before the row's data
+    /// * `data` - the row's data
+    /// * `columns` - the vector to fill in; it's cleared first
+    ///
+    /// Returns the number of bytes of `data` the columns take up. If that's less than the whole
+    /// row, the row is corrupt, just like when a column runs past the end of it; reading and
+    /// validating both check for that.
+    pub fn locate_columns(
+        column_widths: &[u32],
+        bitfield: &[u8],
+        data: &[u8],
+        columns: &mut Vec<Option<Range<usize>>>,
+    ) -> anyhow::Result<usize> {
+        columns.clear();
 
-        // After the length field, is one or more bytes that represent a bit field,
-        // which indicates which, if any, of the columns are actually null, and therefore,
-        // not present.
-        let null_values = Row::read_bitfield(reader, &column_widths)?;
+        let mut offset: usize = 0;
 
-        // Loop over each column definition from the header, and attempt to read that column
-        // for the specific row
+        // Loop over each column definition from the header, and find that column for this
+        // specific row.
         for (index, width) in column_widths.iter().enumerate() {
-            // If the bitmap indicates that for this row, this column is null, we just
-            // push a `None` into the vector, since we need _something_ there, even for
-            // nothing.
-            if null_values[index] {
-                data.push(None);
+            // If the bitmap indicates that for this row, this column is null, it takes up no
+            // space in the row, but we still need _something_ in the vector, even for nothing.
+            if is_null(bitfield, index) {
+                columns.push(None);
                 continue;
             }
 
-            // let mut column: Vec<u8> = vec![];
-
             // If the width for this column is `u32::MAX`, that means it's a variable-width field.
-            // In this case, we need to read a single `u32`, which then tells us how many bytes
-            // to read for the column.
+            // In this case, there's a `u32` at this point, which tells us how long the column is.
             let column_width = if *width == u32::MAX {
-                read_u32(reader)?
+                let length = match u32_at(data, offset) {
+                    Some(length) => length,
+                    None => bail!(
+                        "length of column {} runs past the end of the row",
+                        index + 1
+                    ),
+                };
+
+                offset += 4;
+                length
             } else {
                 *width
             };
 
-            // Now that we know how many bytes to read for this column, let's read them in.
-            let column = read_variable(reader, column_width as usize)?;
+            let end = offset + column_width as usize;
 
-            // And finally wrap the vector in a `Some`, and push it into the row vector.
-            data.push(Some(column));
+            if end > data.len() {
+                bail!("column {} runs past the end of the row", index + 1);
+            }
+
+            columns.push(Some(offset..end));
+            offset = end;
         }
 
-        Ok(Row { null_values, data })
-    }
-
-    /// After the length value at the beginning of a row is one or more bytes that represent
-    /// a bitfield. This bitfield is used to show which columns are null for this row. A 1
-    /// means a column is `null` in this row.
-    fn read_bitfield(
-        mut reader: &mut impl Read,
-        column_widths: &Vec<u32>,
-    ) -> anyhow::Result<Vec<bool>> {
-        let mut null_values: Vec<bool> = vec![];
-
-        // The number of bytes in the bitfield is based on the number of columns, so we have
-        // to compute it.
-        let bitfield_length =
-            (column_widths.len() / 8) + if column_widths.len() % 8 == 0 { 0 } else { 1 };
-        let bitfield = read_variable(&mut reader, bitfield_length as usize)?;
-
-        // Now, loop over each bit in the bitfield, pushing a `true` for `null`s, and a `false`
-        // for present values.
-        for byte in bitfield.iter() {
-            for i in (0..8).rev() {
-                null_values.push(byte & (1 << i) != 0);
-            }
+        Ok(offset)
+    }
+
+    /// Copy the row, so it can be kept after the next row has been read.
+    pub fn to_owned_row(&self) -> OwnedRow {
+        OwnedRow {
+            bitfield: self.bitfield.to_vec(),
+            data: self.data.to_vec(),
+            columns: self.columns.to_vec(),
         }
+    }
+
+    /// The number of columns in the row, including the null ones.
+    #[allow(unused)]
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Whether each column of the row is null, in column order.
+    pub fn null_values(&self) -> impl Iterator<Item = bool> + 'a {
+        let bitfield = self.bitfield;
 
-        Ok(null_values)
+        (0..self.columns.len()).map(move |index| is_null(bitfield, index))
     }
 
-    /// Take a row of data and generate a CSV representation of it.
+    /// The null bitfield from the start of the row.
+    pub fn bitfield(&self) -> &'a [u8] {
+        self.bitfield
+    }
+
+    /// The row's data, after the null bitfield.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Where the column at `index` is in the row's data, or `None` if it's null. The data starts
+    /// straight after the null bitfield. A variable-width column's length comes just before it.
+    pub fn column_range(&self, index: usize) -> Option<Range<usize>> {
+        self.columns[index].clone()
+    }
+
+    /// The raw bytes of the column at `index`, or `None` if it's null.
+    pub fn column(&self, index: usize) -> Option<&'a [u8]> {
+        self.columns[index]
+            .as_ref()
+            .map(|range| &self.data[range.clone()])
+    }
+
+    /// Write the row out as a CSV record.
     ///
+    /// * `writer` - the CSV writer
     /// * `types` - the ColumnTypes struct with conversion info
-    /// * `tz_offset` - number of hours to offset times
-    pub fn generate_csv_output(
+    /// * `columns` - the indexes of the columns to write, in the order to write them
+    /// * `args` - all the command line arguments
+    /// * `scratch` - a buffer that each value is formatted into, before being written. It's
+    ///   passed in, so it can be reused from row to row.
+    pub fn write_csv<W: Write>(
         &self,
+        writer: &mut Writer<W>,
         types: &ColumnTypes,
-        tz_offset: i8,
+        columns: &[usize],
         args: &Args,
-    ) -> anyhow::Result<Vec<String>> {
-        let mut record: Vec<String> = vec![];
+        scratch: &mut String,
+    ) -> anyhow::Result<()> {
+        // Columns that weren't selected are never formatted at all.
+        for &index in columns {
+            let value = match self.column(index) {
+                Some(value) => value,
+                None => {
+                    let null = args.null_string.as_bytes();
+                    write_csv_field(writer, null, false, columns.len(), args)?;
+                    continue;
+                }
+            };
 
-        // Loop over each column, format it, and push it into the vector.
-        for (index, column) in self.data.iter().enumerate() {
-            let column_conversion = &types.column_conversions[index];
+            let column_type = &types.column_types[index];
+            let conversion = &types.column_conversions[index];
 
-            let output =
-                types.column_types[index].format_value(column, tz_offset, column_conversion, &args);
+            // Raw binary values aren't text, so they're written straight from the row.
+            let field = match column_type.raw_bytes(value, conversion, args) {
+                Some(bytes) => bytes,
+                None => {
+                    scratch.clear();
+                    column_type.write_value(
+                        scratch,
+                        Some(value),
+                        conversion,
+                        types.precision(index),
+                        args,
+                    )?;
+                    scratch.as_bytes()
+                }
+            };
 
-            record.push(output);
+            // A value that looks like a null is quoted, so that it isn't loaded as one.
+            let quote = (field.is_empty() && args.quote_empty)
+                || (!args.null_string.is_empty() && field == args.null_string.as_bytes());
+
+            write_csv_field(writer, field, quote, columns.len(), args)?;
         }
 
-        Ok(record)
+        // Passing no fields just ends the record.
+        writer.write_record(None::<&[u8]>)?;
+
+        Ok(())
     }
 
-    /// Take a row of data and generate a JSON representation of it.
+    /// Write the row out as a JSON object.
     ///
+    /// * `writer` - where to write the object
     /// * `types` - the ColumnTypes struct with conversion info
-    /// * `tz_offset` - number of hours to offset times
-    pub fn generate_json_output(
+    /// * `columns` - the indexes of the columns to write, in the order to write them
+    /// * `args` - all the command line arguments
+    /// * `scratch` - a buffer that each value is formatted into, before being written. It's
+    ///   passed in, so it can be reused from row to row.
+    pub fn write_json(
         &self,
+        writer: &mut impl Write,
         types: &ColumnTypes,
-        tz_offset: i8,
+        columns: &[usize],
         args: &Args,
-    ) -> anyhow::Result<String> {
-        let mut record = HashMap::new();
+        scratch: &mut String,
+    ) -> anyhow::Result<()> {
+        writer.write_all(b"{")?;
 
-        for (index, column) in self.data.iter().enumerate() {
-            let column_conversion = &types.column_conversions[index];
+        for (i, &index) in columns.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+
+            serde_json::to_writer(&mut *writer, &types.column_names[index])?;
+            writer.write_all(b":")?;
 
-            let name = types.column_names[index].clone();
-            let value =
-                types.column_types[index].format_value(column, tz_offset, column_conversion, &args);
+            scratch.clear();
 
-            // Generating JSON is more involved than CSV, and the `serde_json` crate requires
-            // wrapping values in a struct that indicates its actual type. So we need to map
-            // Vertica types into `serde_json` types.
-            let mapped_value = match types.column_types[index] {
+            types.column_types[index].write_value(
+                scratch,
+                self.column(index),
+                &types.column_conversions[index],
+                types.precision(index),
+                args,
+            )?;
+
+            // JSON has fewer types than Vertica, so we need to map Vertica types onto JSON's.
+            // Numbers are written as-is, since the formatted value is already valid JSON.
+            match types.column_types[index] {
+                ColumnType::Integer | ColumnType::Numeric | ColumnType::Float
+                    if scratch.is_empty() =>
+                {
+                    writer.write_all(b"null")?
+                }
                 ColumnType::Integer | ColumnType::Numeric => {
-                    if value.is_empty() {
-                        Value::Null
+                    writer.write_all(scratch.as_bytes())?
+                }
+                // Dates and times in an epoch format are numbers, too, unless they're infinite,
+                // or too far away to be converted.
+                ref column_type
+                    if column_type.is_temporal()
+                        && TimeFormat::for_column(&types.column_conversions[index], args)
+                            .is_some_and(|format| format.is_epoch()) =>
+                {
+                    if scratch.is_empty() {
+                        writer.write_all(b"null")?
+                    } else if scratch.parse::<i64>().is_ok() {
+                        writer.write_all(scratch.as_bytes())?
                     } else {
-                        let num = value.parse::<i64>().unwrap();
-                        Value::Number(Number::from(num))
+                        serde_json::to_writer(&mut *writer, scratch.as_str())?
                     }
                 }
-                ColumnType::Float => {
-                    let num = value.parse::<f64>().unwrap();
-                    Value::Number(Number::from_f64(num).unwrap())
-                }
+                // JSON has no numbers for NaN and the infinities, so they're strings, or null.
+                ColumnType::Float => match types.column_types[index].decode(self.column(index)) {
+                    ColumnValue::Float(num) if !num.is_finite() => match args.json_non_finite {
+                        JsonNonFinite::String => {
+                            serde_json::to_writer(&mut *writer, scratch.as_str())?
+                        }
+                        JsonNonFinite::Null => writer.write_all(b"null")?,
+                    },
+                    _ => writer.write_all(scratch.as_bytes())?,
+                },
                 ColumnType::Char
                 | ColumnType::Varchar
                 | ColumnType::Date
@@ -215,35 +798,128 @@ impl Row {
                 | ColumnType::Varbinary
                 | ColumnType::Binary
                 | ColumnType::Interval
-                | ColumnType::UUID => Value::String(value),
-                ColumnType::Boolean => Value::Bool(value == "1"),
-            };
+                | ColumnType::IntervalYearToMonth
+                | ColumnType::Uuid => serde_json::to_writer(&mut *writer, scratch.as_str())?,
+                // Booleans are always JSON's true or false, whatever --bool-format says.
+                ColumnType::Boolean => {
+                    let value = types.column_types[index].decode(self.column(index));
+                    serde_json::to_writer(&mut *writer, &(value == ColumnValue::Boolean(true)))?
+                }
+            }
+        }
+
+        writer.write_all(b"}")?;
+
+        Ok(())
+    }
+}
+
+/// A CSV writer with the delimiter and quote from the command line. It never quotes fields
+/// itself; `write_csv_field` does, so that some empty fields can be quoted, and others not.
+///
+/// * `writer` - where to write the CSV
+/// * `args` - the command line arguments
+pub fn csv_writer<W: Write>(writer: W, args: &Args) -> Writer<W> {
+    WriterBuilder::new()
+        .delimiter(args.delimiter)
+        .quote_style(QuoteStyle::Never)
+        .from_writer(writer)
+}
+
+/// Write one field of a CSV record, from a writer made by `csv_writer`. It's quoted if it has
+/// the delimiter, the quote character, or a line break in it, like the `csv` crate would, or if
+/// `quote` is set. Like the `csv` crate, a record with just one field that's empty is always
+/// quoted, so that it isn't written as a blank line, which readers would skip.
+///
+/// * `writer` - the CSV writer
+/// * `field` - the field's bytes; usually text, but a raw binary value can be anything
+/// * `quote` - whether to quote the field, even if it doesn't need it
+/// * `field_count` - the number of fields in the record
+/// * `args` - the command line arguments
+pub fn write_csv_field<W: Write>(
+    writer: &mut Writer<W>,
+    field: &[u8],
+    quote: bool,
+    field_count: usize,
+    args: &Args,
+) -> csv::Result<()> {
+    let quote_char = if args.single_quotes { b'\'' } else { b'"' };
+
+    let quote = quote
+        || (field.is_empty() && field_count == 1)
+        || field
+            .iter()
+            .any(|b| *b == args.delimiter || *b == quote_char || *b == b'\r' || *b == b'\n');
+
+    if !quote {
+        return writer.write_field(field);
+    }
 
-            record.insert(name, mapped_value);
+    // Quotes inside the field are doubled.
+    let mut quoted = Vec::with_capacity(field.len() + 2);
+    quoted.push(quote_char);
+
+    for b in field {
+        if *b == quote_char {
+            quoted.push(quote_char);
         }
 
-        // Use the `serde_json` crate to convert the `HashMap` into a JSON string
-        let str_record = serde_json::to_string(&record).unwrap();
+        quoted.push(*b);
+    }
+
+    quoted.push(quote_char);
+
+    writer.write_field(quoted)
+}
 
-        Ok(str_record)
+/// A copy of a `Row`, which doesn't borrow from the file, for holding on to rows while others
+/// are read, as when sampling.
+#[derive(Debug)]
+pub struct OwnedRow {
+    bitfield: Vec<u8>,
+    data: Vec<u8>,
+    columns: Vec<Option<Range<usize>>>,
+}
+
+impl OwnedRow {
+    /// The copied row, as a `Row`, for writing out.
+    pub fn row(&self) -> Row<'_> {
+        Row {
+            bitfield: &self.bitfield,
+            data: &self.data,
+            columns: &self.columns,
+        }
     }
 }
 
+/// Check the null bitfield for the column at `index`. The bits are in column order, starting
+/// with the most significant bit of the first byte. A 1 means the column is `null` in this row.
+pub fn is_null(bitfield: &[u8], index: usize) -> bool {
+    bitfield[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
-    use std::io::BufReader;
+    use std::convert::TryInto;
+    use std::fs::{self, File};
+    use std::io::{BufReader, Cursor};
 
-    use crate::vertica_native_file::VerticaNativeFile;
+    use crate::assemble::assemble;
+    use crate::binary_encoding::BinaryEncoding;
+    use crate::column_type::ColumnType;
+    use crate::column_types::ColumnTypes;
+    use crate::index::{FileStamp, RowIndex};
+    use crate::vertica_native_file::{csv_writer, VerticaNativeFile};
+    use crate::Args;
 
     #[test]
     fn test_read_from_good_file() {
         let mut file = BufReader::new(File::open("data/all-types.bin").unwrap());
 
-        let file = VerticaNativeFile::from_reader(&mut file).unwrap();
+        let mut file = VerticaNativeFile::from_reader(&mut file).unwrap();
 
-        for row in file {
-            assert_eq!(14, row.data.len());
+        while let Some(row) = file.next_row() {
+            assert_eq!(14, row.column_count());
         }
     }
 
@@ -251,10 +927,333 @@ mod tests {
     fn test_read_from_good_file_with_nulls() {
         let mut file = BufReader::new(File::open("data/all-types-with-nulls.bin").unwrap());
 
-        let file = VerticaNativeFile::from_reader(&mut file).unwrap();
+        let mut file = VerticaNativeFile::from_reader(&mut file).unwrap();
+
+        let row = file.next_row().unwrap();
+
+        assert_eq!(14, row.column_count());
+        assert_eq!(None, row.column(1));
+        assert_eq!(Some(&b"ONE"[..]), row.column(3));
+    }
+
+    #[test]
+    fn test_read_from_bytes() {
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+
+        let mut count = 0;
 
-        for row in file {
-            assert_eq!(14, row.data.len());
+        while let Some(row) = file.next_row() {
+            assert_eq!(14, row.column_count());
+            assert_eq!(Some(&b"ONE"[..]), row.column(3));
+            count += 1;
         }
+
+        assert_eq!(10, count);
+    }
+
+    /// The ten-row test file, with the length of the `varchar` in the third row set to `length`
+    fn corrupt_third_row(length: u32) -> Vec<u8> {
+        let mut bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+
+        let first_row = 76;
+        let row_length = u32::from_le_bytes(bytes[first_row..first_row + 4].try_into().unwrap());
+        let third_row = first_row + 2 * (4 + 2 + row_length as usize);
+
+        // The `varchar` comes after two 8-byte columns and a 10-byte `char`.
+        let offset = third_row + 4 + 2 + 26;
+        bytes[offset..offset + 4].copy_from_slice(&length.to_le_bytes());
+
+        bytes
+    }
+
+    fn count_rows(file: &mut VerticaNativeFile) -> usize {
+        let mut count = 0;
+
+        while let Some(row) = file.next_row() {
+            assert_eq!(Some(&b"ONE"[..]), row.column(3));
+            count += 1;
+        }
+
+        count
+    }
+
+    #[test]
+    fn test_stop_at_bad_row() {
+        // Too long, and too short
+        for length in &[1000, 1] {
+            let bytes = corrupt_third_row(*length);
+
+            let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+            assert_eq!(2, count_rows(&mut file));
+
+            let mut reader = &bytes[..];
+            let mut file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+            assert!(file.read_row().is_ok());
+            assert!(file.read_row().is_ok());
+            let message = file.read_row().unwrap_err().to_string();
+            assert!(
+                message.starts_with("row 3 at offset 318: row length is 115, but "),
+                "{}",
+                message
+            );
+
+            // The `varchar` is the 4th column, counting from 1, like everywhere else.
+            if *length == 1000 {
+                assert!(message.ends_with(", but column 4 runs past the end of the row"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stop_at_bad_row_length() {
+        let mut bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+        bytes[318..322].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut reader = &bytes[..];
+        let mut file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+        assert!(file.read_row().is_ok());
+        assert!(file.read_row().is_ok());
+        let message = file.read_row().unwrap_err().to_string();
+        assert_eq!(
+            "row 3 at offset 318: row length is 4294967295, which is more than any row can be",
+            message
+        );
+
+        let mut reader = Cursor::new(&bytes);
+        let mut file = VerticaNativeFile::from_seekable_reader(&mut reader).unwrap();
+        assert!(file.read_row().is_ok());
+        assert!(file.read_row().is_ok());
+        let message = file.read_row().unwrap_err().to_string();
+        assert_eq!(
+            format!(
+                "row 3 at offset 318: row length is 4294967295, but there are only {} bytes left in the file",
+                bytes.len() - 322
+            ),
+            message
+        );
+    }
+
+    #[test]
+    fn test_read_all_null_row() {
+        let bytes = fs::read("data/all-types-with-null-row.bin").unwrap();
+
+        let mut files = vec![VerticaNativeFile::from_bytes(&bytes).unwrap()];
+        let mut reader = &bytes[..];
+        files.push(VerticaNativeFile::from_reader(&mut reader).unwrap());
+
+        for file in &mut files {
+            let mut count = 0;
+
+            while let Some(row) = file.next_row() {
+                count += 1;
+                assert_eq!(count == 6, row.data().is_empty());
+                assert_eq!(count == 6, (0..14).all(|index| row.column(index).is_none()));
+            }
+
+            assert_eq!(11, count);
+        }
+    }
+
+    #[test]
+    fn test_stop_at_partial_row_length() {
+        let mut bytes = fs::read("data/all-types.bin").unwrap();
+        bytes.extend_from_slice(&[1, 0]);
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        assert!(file.read_row().unwrap().is_some());
+        let message = file.read_row().unwrap_err().to_string();
+        assert_eq!(
+            "row 2 at offset 197: 2 bytes left over at the end of the file",
+            message
+        );
+    }
+
+    #[test]
+    fn test_skip_bad_row() {
+        for length in &[1000, 1] {
+            let bytes = corrupt_third_row(*length);
+
+            let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+            file.skip_bad_rows = true;
+            assert_eq!(9, count_rows(&mut file));
+
+            let mut reader = &bytes[..];
+            let mut file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+            file.skip_bad_rows = true;
+            assert_eq!(9, count_rows(&mut file));
+        }
+    }
+
+    #[test]
+    fn test_skip_rows() {
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        assert_eq!(2, file.skip_rows(2).unwrap());
+        assert!(file.next_row().is_some());
+        assert_eq!(3, file.row_number);
+        assert_eq!(318, file.row_offset);
+        assert_eq!(7, count_rows(&mut file));
+
+        let mut reader = &bytes[..];
+        let mut file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+        assert_eq!(10, file.skip_rows(20).unwrap());
+        assert!(file.next_row().is_none());
+    }
+
+    #[test]
+    fn test_skip_rows_by_seeking() {
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+
+        let mut reader = Cursor::new(&bytes);
+        let mut file = VerticaNativeFile::from_seekable_reader(&mut reader).unwrap();
+        assert_eq!(2, file.skip_rows(2).unwrap());
+        assert!(file.next_row().is_some());
+        assert_eq!((3, 318), (file.row_number, file.row_offset));
+        assert_eq!(7, file.skip_rows(20).unwrap());
+
+        // A short file is still found out, before seeking past its end.
+        let mut reader = Cursor::new(&bytes[..bytes.len() - 10]);
+        let mut file = VerticaNativeFile::from_seekable_reader(&mut reader).unwrap();
+        let message = file.skip_rows(20).unwrap_err().to_string();
+        assert!(
+            message.starts_with("row 10 at offset 1165: "),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_last_row() {
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        file.last_row = 4;
+        assert_eq!(3, file.skip_rows(3).unwrap());
+        assert_eq!(1, count_rows(&mut file));
+    }
+
+    #[test]
+    fn test_seek_to_row() {
+        let bytes = fs::read("data/all-types-ten-rows.bin").unwrap();
+        let index = RowIndex::build(
+            &mut VerticaNativeFile::from_bytes(&bytes).unwrap(),
+            3,
+            FileStamp {
+                size: 0,
+                modified: Default::default(),
+            },
+        )
+        .unwrap();
+
+        let mut reader = Cursor::new(&bytes);
+        let mut file = VerticaNativeFile::from_seekable_reader(&mut reader).unwrap();
+
+        for index in [None, Some(index)] {
+            file.index = index;
+
+            for row in [8, 2, 10, 1, 11] {
+                file.seek_to_row(row).unwrap();
+
+                if row > 10 {
+                    assert!(file.next_row().is_none());
+                } else {
+                    assert!(file.next_row().is_some());
+                    assert_eq!(row, file.row_number());
+                    assert_eq!(76 + (row as u64 - 1) * 121, file.row_offset());
+                }
+            }
+        }
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        file.seek_to_row(9).unwrap();
+        file.seek_to_row(3).unwrap();
+        assert_eq!(8, count_rows(&mut file));
+
+        let mut reader = &bytes[..];
+        let mut file = VerticaNativeFile::from_reader(&mut reader).unwrap();
+        file.seek_to_row(5).unwrap();
+        assert_eq!(6, count_rows(&mut file));
+        assert!(file.seek_to_row(2).is_err());
+    }
+
+    #[test]
+    fn test_write_csv_raw_binary() {
+        let bytes =
+            assemble("@signature\n@header 8 var\n@row\n@nulls\nint64:1 varbinary:ff00412c22\n")
+                .unwrap();
+        let types = ColumnTypes {
+            column_types: vec![ColumnType::Integer, ColumnType::Varbinary],
+            column_names: vec!["Id".to_string(), "Bytes".to_string()],
+            column_conversions: vec![None, None],
+            column_parameters: vec![vec![], vec![]],
+        };
+        let mut args = Args::with_defaults();
+        args.binary_encoding = BinaryEncoding::Raw;
+
+        let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+        let mut writer = csv_writer(vec![], &args);
+        let row = file.next_row().unwrap();
+        row.write_csv(&mut writer, &types, &[0, 1], &args, &mut String::new())
+            .unwrap();
+
+        // Every byte is kept, with the field quoted, since it has a comma and a quote in it.
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            b"1,\"\xff\x00A,\"\"\"\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_write_csv_nulls() {
+        let bytes = assemble(
+            r#"
+            @signature
+            @header 8 var
+            @row
+            @nulls 2
+            int64:1
+            @row
+            @nulls
+            int64:2 varchar:""
+            @row
+            @nulls
+            int64:3 varchar:NULL
+            @row
+            @nulls
+            int64:4 varchar:"a,b"
+            "#,
+        )
+        .unwrap();
+
+        let types =
+            ColumnTypes::from_reader(BufReader::new(File::open("data/id-and-name.txt").unwrap()))
+                .unwrap();
+
+        let write = |args: &Args| {
+            let mut file = VerticaNativeFile::from_bytes(&bytes).unwrap();
+            let mut writer = csv_writer(vec![], args);
+            let mut scratch = String::new();
+
+            while let Some(row) = file.next_row() {
+                row.write_csv(&mut writer, &types, &[0, 1], args, &mut scratch)
+                    .unwrap();
+            }
+
+            String::from_utf8(writer.into_inner().unwrap()).unwrap()
+        };
+
+        let mut args = Args::with_defaults();
+        assert_eq!(write(&args), "1,\n2,\n3,NULL\n4,\"a,b\"\n");
+
+        args.null_string = "NULL".to_string();
+        args.quote_empty = true;
+        assert_eq!(write(&args), "1,NULL\n2,\"\"\n3,\"NULL\"\n4,\"a,b\"\n");
+
+        args.null_string = "\\N".to_string();
+        args.single_quotes = true;
+        assert_eq!(write(&args), "1,\\N\n2,''\n3,NULL\n4,'a,b'\n");
     }
 }